@@ -0,0 +1,717 @@
+use wasm_bindgen::prelude::*;
+
+use crate::error::{BarcodeError, ErrorKind};
+use crate::gs1::{days_in_month, days_since_epoch};
+use crate::BarcodeResult;
+
+/// Separates the data elements of a subfile
+const DATA_ELEMENT_SEPARATOR: char = '\n';
+
+/// Ends each subfile
+const SEGMENT_TERMINATOR: char = '\r';
+
+/// The compliance indicator and separators every header starts with
+const HEADER_START: &str = "@\n\u{1e}\r";
+
+/// The newest version of the AAMVA DL/ID Card Design Standard, from 2020
+const LATEST_VERSION: u8 = 10;
+
+/// Elements every DL or ID subfile should have, with the names for validation errors
+const MANDATORY: [(&str, &str); 5] = [
+    ("DAQ", "customer ID number"),
+    ("DBA", "expiration date"),
+    ("DBB", "date of birth"),
+    ("DBC", "sex"),
+    ("DBD", "issue date"),
+];
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The cardholder data in the PDF417 on the back of a North American driver's license or ID card,
+/// as laid out by the AAMVA DL/ID Card Design Standard, versions 1 to 10.
+///
+/// The typed getters read the first DL or ID subfile and hide the differences between versions,
+/// such as where the names are and which order dates are written in. Anything else is available
+/// by its three letter element ID from `element` and `subfile`.
+pub struct AamvaRecord {
+    issuer_id: String,
+    version: u8,
+    jurisdiction_version: Option<u8>,
+    subfiles: Vec<Subfile>,
+    validation_errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Subfile {
+    subfile_type: String,
+    elements: Vec<(String, String)>,
+}
+
+/// A date as (year, month, day)
+type Date = (i32, u32, u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sex {
+    Male,
+    Female,
+    Unspecified,
+}
+
+#[wasm_bindgen]
+impl AamvaRecord {
+    /// Parse the text of a license or ID card PDF417.
+    ///
+    /// Fails with a `Format` error if the text doesn't have an AAMVA header, is of a version
+    /// other than 1 to 10, or has no DL or ID subfile. Problems that still leave the card
+    /// readable, such as a missing element or an impossible date, are listed by
+    /// `validation_errors` instead.
+    pub fn parse(text: &str) -> Result<AamvaRecord, BarcodeError> {
+        parse(text)
+    }
+
+    /// The six digit Issuer Identification Number of the jurisdiction that issued the card
+    pub fn issuer_id(&self) -> String {
+        self.issuer_id.to_owned()
+    }
+
+    /// The version of the AAMVA standard the card follows, from 1 to 10
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The jurisdiction's own version of its card layout. Version 1 cards don't have one.
+    pub fn jurisdiction_version(&self) -> Option<u8> {
+        self.jurisdiction_version
+    }
+
+    /// "DL" for a driver's license or "ID" for an identification card
+    pub fn document_type(&self) -> String {
+        self.card().subfile_type.to_owned()
+    }
+
+    /// The type of each subfile in the order of the header, such as "DL" and the jurisdiction
+    /// specific "ZV"
+    pub fn subfile_types(&self) -> Vec<String> {
+        self.subfiles
+            .iter()
+            .map(|subfile| subfile.subfile_type.to_owned())
+            .collect()
+    }
+
+    /// The value of a data element such as "DAQ", from the DL or ID subfile or else the first
+    /// other subfile that has it
+    pub fn element(&self, id: &str) -> Option<String> {
+        std::iter::once(self.card())
+            .chain(&self.subfiles)
+            .find_map(|subfile| subfile.get(id))
+            .map(str::to_owned)
+    }
+
+    /// The elements of the DL or ID subfile, keyed by element ID
+    pub fn elements(&self) -> js_sys::Map {
+        self.card().to_map()
+    }
+
+    /// The elements of the first subfile of the given type, keyed by element ID
+    pub fn subfile(&self, subfile_type: &str) -> Option<js_sys::Map> {
+        self.subfiles
+            .iter()
+            .find(|subfile| subfile.subfile_type == subfile_type)
+            .map(Subfile::to_map)
+    }
+
+    pub fn family_name(&self) -> Option<String> {
+        self.family_name_of()
+    }
+
+    pub fn first_name(&self) -> Option<String> {
+        self.first_name_of()
+    }
+
+    /// All middle names, as the card writes them
+    pub fn middle_name(&self) -> Option<String> {
+        self.middle_name_of()
+    }
+
+    /// A suffix such as "JR" or "III"
+    pub fn name_suffix(&self) -> Option<String> {
+        self.text("DCU")
+    }
+
+    /// The customer ID number, which most jurisdictions print as the license number
+    pub fn customer_id(&self) -> Option<String> {
+        self.text("DAQ")
+    }
+
+    /// The number that tells apart cards issued to the same person
+    pub fn document_discriminator(&self) -> Option<String> {
+        self.text("DCF")
+    }
+
+    /// Midnight UTC on the date of birth
+    pub fn date_of_birth(&self) -> Option<js_sys::Date> {
+        self.date("DBB").map(utc_date)
+    }
+
+    /// Midnight UTC on the day the card was issued
+    pub fn issue_date(&self) -> Option<js_sys::Date> {
+        self.date("DBD").map(utc_date)
+    }
+
+    /// Midnight UTC on the day the card expires
+    pub fn expiration_date(&self) -> Option<js_sys::Date> {
+        self.date("DBA").map(utc_date)
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "\"male\" | \"female\" | \"unspecified\" | undefined")]
+    /// The sex printed on the card, undefined if it is missing or not a known code
+    pub fn sex(&self) -> Option<String> {
+        self.sex_of().map(|sex| {
+            match sex {
+                Sex::Male => "male",
+                Sex::Female => "female",
+                Sex::Unspecified => "unspecified",
+            }
+            .to_owned()
+        })
+    }
+
+    pub fn street(&self) -> Option<String> {
+        self.text("DAG")
+    }
+
+    /// The second line of the street address
+    pub fn street_2(&self) -> Option<String> {
+        self.text("DAH")
+    }
+
+    pub fn city(&self) -> Option<String> {
+        self.text("DAI")
+    }
+
+    /// The two letter code of the state or province of the address
+    pub fn jurisdiction(&self) -> Option<String> {
+        self.text("DAJ")
+    }
+
+    pub fn postal_code(&self) -> Option<String> {
+        self.text("DAK")
+    }
+
+    /// "USA" or "CAN"
+    pub fn country(&self) -> Option<String> {
+        self.text("DCG")
+    }
+
+    /// The vehicle classes the license covers, such as "C"
+    pub fn vehicle_class(&self) -> Option<String> {
+        self.text("DCA")
+    }
+
+    pub fn restrictions(&self) -> Option<String> {
+        self.text("DCB")
+    }
+
+    pub fn endorsements(&self) -> Option<String> {
+        self.text("DCD")
+    }
+
+    pub fn organ_donor(&self) -> bool {
+        self.text("DDK").as_deref() == Some("1")
+    }
+
+    pub fn veteran(&self) -> bool {
+        self.text("DDL").as_deref() == Some("1")
+    }
+
+    /// What is wrong with a card that could still be read, such as a missing mandatory
+    /// element, a date that doesn't exist or a subfile that isn't where the header says.
+    /// Empty for a well formed card.
+    pub fn validation_errors(&self) -> Vec<String> {
+        self.validation_errors.to_vec()
+    }
+}
+
+impl AamvaRecord {
+    /// The first DL or ID subfile, which parsing makes sure there is
+    fn card(&self) -> &Subfile {
+        self.subfiles
+            .iter()
+            .find(|subfile| is_card_subfile(&subfile.subfile_type))
+            .unwrap_or(&self.subfiles[0])
+    }
+
+    /// A DL or ID element, left out when it is empty
+    fn text(&self, id: &str) -> Option<String> {
+        self.card()
+            .get(id)
+            .filter(|value| !value.is_empty())
+            .map(str::to_owned)
+    }
+
+    /// Version 1 has the whole name comma separated in DAA, or the parts in DAB, DAC and DAD.
+    /// Versions 2 and 3 have the family name in DCS and the given names together in DCT, and
+    /// version 4 on has DCS, DAC and DAD.
+    fn full_name(&self, part: usize) -> Option<String> {
+        let name = self.text("DAA")?;
+        let name = name.split(',').nth(part)?.trim();
+        (!name.is_empty()).then(|| name.to_owned())
+    }
+
+    fn given_names(&self) -> Option<(String, Option<String>)> {
+        let names = self.text("DCT")?;
+        let separator = if names.contains(',') { ',' } else { ' ' };
+        let (first, middle) = match names.split_once(separator) {
+            Some((first, middle)) => (first, Some(middle.trim().to_owned())),
+            None => (names.as_str(), None),
+        };
+        Some((
+            first.trim().to_owned(),
+            middle.filter(|middle| !middle.is_empty()),
+        ))
+    }
+
+    fn family_name_of(&self) -> Option<String> {
+        self.text("DCS")
+            .or_else(|| self.text("DAB"))
+            .or_else(|| self.full_name(0))
+    }
+
+    fn first_name_of(&self) -> Option<String> {
+        self.text("DAC")
+            .or_else(|| self.given_names().map(|(first, _)| first))
+            .or_else(|| self.full_name(1))
+    }
+
+    fn middle_name_of(&self) -> Option<String> {
+        self.text("DAD")
+            .or_else(|| self.given_names().and_then(|(_, middle)| middle))
+            .or_else(|| self.full_name(2))
+    }
+
+    /// Version 1 and Canadian cards write dates as CCYYMMDD and later US cards as MMDDCCYY.
+    /// Some cards use the other order from their version, so that is tried when the expected
+    /// one doesn't give a real date.
+    fn date(&self, id: &str) -> Option<Date> {
+        let value = self.text(id)?;
+        let year_first = self.version == 1 || self.text("DCG").as_deref() == Some("CAN");
+        parse_date(&value, year_first).or_else(|| parse_date(&value, !year_first))
+    }
+
+    fn sex_of(&self) -> Option<Sex> {
+        match self.text("DBC")?.as_str() {
+            "1" | "M" => Some(Sex::Male),
+            "2" | "F" => Some(Sex::Female),
+            // 9 is "not specified", and some jurisdictions write X
+            "9" | "X" => Some(Sex::Unspecified),
+            _ => None,
+        }
+    }
+
+    fn validate(&mut self) {
+        let mut errors = Vec::new();
+        for (id, name) in MANDATORY {
+            match self.text(id) {
+                None => errors.push(format!("the {name} ({id}) is missing")),
+                Some(value) if id == "DBC" && self.sex_of().is_none() => {
+                    errors.push(format!("the sex (DBC) \"{value}\" is not a known code"));
+                }
+                Some(value) if id != "DAQ" && id != "DBC" && self.date(id).is_none() => {
+                    errors.push(format!("the {name} ({id}) \"{value}\" is not a date"));
+                }
+                Some(_) => {}
+            }
+        }
+        if self.family_name_of().is_none() {
+            errors.push("the family name is missing".to_owned());
+        }
+        if self.first_name_of().is_none() {
+            errors.push("the first name is missing".to_owned());
+        }
+        self.validation_errors.append(&mut errors);
+    }
+}
+
+impl Subfile {
+    fn get(&self, id: &str) -> Option<&str> {
+        self.elements
+            .iter()
+            .find(|(element_id, _)| element_id == id)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn to_map(&self) -> js_sys::Map {
+        let map = js_sys::Map::new();
+        for (id, value) in &self.elements {
+            map.set(&JsValue::from_str(id), &JsValue::from_str(value));
+        }
+        map
+    }
+}
+
+#[wasm_bindgen]
+impl BarcodeResult {
+    /// Parse the text as the AAMVA data of a North American driver's license or ID card. See
+    /// `AamvaRecord.parse`.
+    pub fn aamva_record(&self) -> Result<AamvaRecord, BarcodeError> {
+        parse(&self.text)
+    }
+}
+
+fn parse(text: &str) -> Result<AamvaRecord, BarcodeError> {
+    let mut validation_errors = Vec::new();
+    if !text.starts_with('@') {
+        return Err(format_error(
+            "not AAMVA card data: it doesn't start with the compliance indicator \"@\"",
+        ));
+    }
+    // older cards say "AAMVA" rather than "ANSI", and some drop the space after it
+    let file_type = text
+        .get(..HEADER_START.len() + 8)
+        .and_then(|start| start.find("ANSI").or_else(|| start.find("AAMVA")))
+        .ok_or_else(|| format_error("not AAMVA card data: the header has no file type"))?;
+    if !text.starts_with(HEADER_START) {
+        validation_errors.push(
+            "the header separators after \"@\" are not line feed, record separator and carriage return"
+                .to_owned(),
+        );
+    }
+    let mut header = Header {
+        text,
+        position: file_type
+            + if text[file_type..].starts_with("ANSI") {
+                4
+            } else {
+                5
+            },
+    };
+    if text[header.position..].starts_with(' ') {
+        header.position += 1;
+    }
+
+    let issuer_id = header.digits(6, "issuer identification number")?.to_owned();
+    let version = header.number(2, "version")?;
+    if !(1..=LATEST_VERSION).contains(&version) {
+        return Err(format_error(format!(
+            "AAMVA version {version} is not supported, only versions 1 to {LATEST_VERSION} are"
+        )));
+    }
+    let jurisdiction_version = match version {
+        1 => None,
+        _ => Some(header.number(2, "jurisdiction version")?),
+    };
+    let entries = header.number(2, "number of entries")?;
+
+    let mut designators = Vec::new();
+    for _ in 0..entries {
+        let subfile_type = header.take(2, "subfile type")?.to_owned();
+        if !subfile_type.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(format_error(format!(
+                "the header lists a subfile of type \"{subfile_type}\""
+            )));
+        }
+        let offset = header.digits(4, "subfile offset")?;
+        let offset: usize = offset.parse().unwrap_or_default();
+        header.digits(4, "subfile length")?;
+        designators.push((subfile_type, offset));
+    }
+
+    let mut subfiles = Vec::new();
+    let mut search_from = header.position;
+    for (subfile_type, offset) in designators {
+        let start = if text.get(offset..offset + 2) == Some(subfile_type.as_str()) {
+            offset
+        } else {
+            // many jurisdictions get the offsets wrong, so look for the subfile after the last one
+            match text[search_from..].find(&subfile_type) {
+                Some(found) => {
+                    validation_errors.push(format!(
+                        "the {subfile_type} subfile is not at offset {offset} as the header says"
+                    ));
+                    search_from + found
+                }
+                None => {
+                    validation_errors.push(format!("the {subfile_type} subfile is missing"));
+                    continue;
+                }
+            }
+        };
+        let data = &text[start + 2..];
+        let end = data.find(SEGMENT_TERMINATOR).unwrap_or(data.len());
+        search_from = start + 2 + end;
+        subfiles.push(Subfile {
+            elements: elements(&data[..end], &subfile_type, &mut validation_errors),
+            subfile_type,
+        });
+    }
+
+    if !subfiles
+        .iter()
+        .any(|subfile| is_card_subfile(&subfile.subfile_type))
+    {
+        return Err(format_error(
+            "the card data has no DL or ID subfile with the cardholder's details",
+        ));
+    }
+    let mut record = AamvaRecord {
+        issuer_id,
+        version,
+        jurisdiction_version,
+        subfiles,
+        validation_errors,
+    };
+    record.validate();
+    Ok(record)
+}
+
+/// Split subfile data into (element ID, value) pairs, trimming the padding that fixed length
+/// fields are written with
+fn elements(
+    data: &str,
+    subfile_type: &str,
+    validation_errors: &mut Vec<String>,
+) -> Vec<(String, String)> {
+    data.split(DATA_ELEMENT_SEPARATOR)
+        .filter(|element| !element.trim().is_empty())
+        .filter_map(|element| match element.get(..3) {
+            Some(id)
+                if id
+                    .bytes()
+                    .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit()) =>
+            {
+                Some((id.to_owned(), element[3..].trim().to_owned()))
+            }
+            _ => {
+                validation_errors.push(format!(
+                    "the {subfile_type} subfile has a malformed element \"{}\"",
+                    element.trim()
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reads the fixed width fields of the header in turn
+struct Header<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Header<'a> {
+    fn take(&mut self, length: usize, field: &str) -> Result<&'a str, BarcodeError> {
+        let value = self
+            .text
+            .get(self.position..self.position + length)
+            .ok_or_else(|| format_error(format!("the header ends before the {field}")))?;
+        self.position += length;
+        Ok(value)
+    }
+
+    fn digits(&mut self, length: usize, field: &str) -> Result<&'a str, BarcodeError> {
+        let value = self.take(length, field)?;
+        if !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format_error(format!(
+                "the {field} in the header is \"{value}\" rather than {length} digits"
+            )));
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self, length: usize, field: &str) -> Result<u8, BarcodeError> {
+        // at most two digits, so it always fits
+        Ok(self.digits(length, field)?.parse().unwrap_or_default())
+    }
+}
+
+fn is_card_subfile(subfile_type: &str) -> bool {
+    subfile_type == "DL" || subfile_type == "ID"
+}
+
+/// An eight digit date as CCYYMMDD when `year_first`, otherwise as MMDDCCYY
+fn parse_date(value: &str, year_first: bool) -> Option<Date> {
+    if value.len() != 8 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| value[range].parse().unwrap_or_default();
+    let (year, month, day) = if year_first {
+        (number(0..4), number(4..6), number(6..8))
+    } else {
+        (number(4..8), number(0..2), number(2..4))
+    };
+    let valid = (1..=12).contains(&month) && (1..=days_in_month(year as i32, month)).contains(&day);
+    valid.then_some((year as i32, month, day))
+}
+
+fn utc_date((year, month, day): Date) -> js_sys::Date {
+    let milliseconds = days_since_epoch(year, month, day) as f64 * 86_400_000.0;
+    js_sys::Date::new(&JsValue::from_f64(milliseconds))
+}
+
+fn format_error(message: impl Into<String>) -> BarcodeError {
+    BarcodeError::new(ErrorKind::Format, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Card data with a header listing `subfiles` at the offsets they end up at
+    fn card_data(version: u8, subfiles: &[(&str, &str)]) -> String {
+        let mut header = format!("{HEADER_START}ANSI 636014{version:02}");
+        if version > 1 {
+            header.push_str("00");
+        }
+        header.push_str(&format!("{:02}", subfiles.len()));
+        let mut offset = header.len() + subfiles.len() * 10;
+        let mut body = String::new();
+        for (subfile_type, elements) in subfiles {
+            let subfile = format!("{subfile_type}{elements}{SEGMENT_TERMINATOR}");
+            header.push_str(&format!("{subfile_type}{offset:04}{:04}", subfile.len()));
+            offset += subfile.len();
+            body.push_str(&subfile);
+        }
+        header + &body
+    }
+
+    const US_CARD: &str = "DAQD1234567\nDCSSAMPLE\nDACJOHN\nDADQUINCY\nDBA01152030\nDBB07041990\n\
+                           DBD01152022\nDBC1\nDCGUSA\n";
+
+    const CANADIAN_CARD: &str = "DAQD1234567\nDCSSAMPLE\nDACJEAN\nDBA20300115\nDBB19900704\n\
+                                 DBD20220115\nDBCF\nDCGCAN\n";
+
+    #[test]
+    fn parses_the_header_and_subfiles() {
+        let text = card_data(10, &[("DL", US_CARD), ("ZV", "ZVAFOO\n")]);
+        let record = parse(&text).unwrap();
+        assert_eq!(record.issuer_id(), "636014");
+        assert_eq!(record.version(), 10);
+        assert_eq!(record.jurisdiction_version(), Some(0));
+        assert_eq!(record.document_type(), "DL");
+        assert_eq!(record.subfile_types(), ["DL", "ZV"]);
+        assert_eq!(record.element("ZVA").as_deref(), Some("FOO"));
+        assert_eq!(record.family_name().as_deref(), Some("SAMPLE"));
+        assert_eq!(record.first_name().as_deref(), Some("JOHN"));
+        assert_eq!(record.middle_name().as_deref(), Some("QUINCY"));
+        assert_eq!(record.sex().as_deref(), Some("male"));
+        assert!(record.validation_errors().is_empty());
+    }
+
+    #[test]
+    fn us_dates_are_month_first_and_canadian_dates_year_first() {
+        let us = parse(&card_data(10, &[("DL", US_CARD)])).unwrap();
+        assert_eq!(us.date("DBB"), Some((1990, 7, 4)));
+        assert_eq!(us.date("DBA"), Some((2030, 1, 15)));
+        let canadian = parse(&card_data(10, &[("ID", CANADIAN_CARD)])).unwrap();
+        assert_eq!(canadian.date("DBB"), Some((1990, 7, 4)));
+        assert_eq!(canadian.date("DBA"), Some((2030, 1, 15)));
+        assert!(canadian.validation_errors().is_empty());
+    }
+
+    #[test]
+    fn a_date_in_the_other_order_is_still_read() {
+        let data = US_CARD.replace("DBB07041990", "DBB19900704");
+        let record = parse(&card_data(10, &[("DL", &data)])).unwrap();
+        assert_eq!(record.date("DBB"), Some((1990, 7, 4)));
+    }
+
+    #[test]
+    fn version_1_dates_are_year_first() {
+        let data = "DAQD1234567\nDAASAMPLE,JOHN,QUINCY\nDBA20300115\nDBB19900704\nDBD20220115\n\
+                    DBCM\n";
+        let record = parse(&card_data(1, &[("DL", data)])).unwrap();
+        assert_eq!(record.jurisdiction_version(), None);
+        assert_eq!(record.date("DBB"), Some((1990, 7, 4)));
+        assert_eq!(record.family_name().as_deref(), Some("SAMPLE"));
+        assert_eq!(record.middle_name().as_deref(), Some("QUINCY"));
+    }
+
+    #[test]
+    fn parses_dates_in_either_order() {
+        assert_eq!(parse_date("10111213", false), Some((1213, 10, 11)));
+        assert_eq!(parse_date("10111213", true), Some((1011, 12, 13)));
+        assert_eq!(parse_date("02292024", false), Some((2024, 2, 29)));
+        assert_eq!(parse_date("02292023", false), None);
+        assert_eq!(parse_date("19901304", true), None);
+        assert_eq!(parse_date("1990070", true), None);
+        assert_eq!(parse_date("1990-7-4", true), None);
+    }
+
+    #[test]
+    fn finds_a_subfile_at_the_wrong_offset() {
+        let text = card_data(10, &[("DL", US_CARD)]);
+        // one byte of stray padding moves the subfile off its offset
+        let text = text.replacen("DLDAQ", " DLDAQ", 1);
+        let record = parse(&text).unwrap();
+        assert_eq!(record.first_name().as_deref(), Some("JOHN"));
+        assert_eq!(
+            record.validation_errors(),
+            ["the DL subfile is not at offset 31 as the header says"]
+        );
+    }
+
+    #[test]
+    fn reports_a_truncated_subfile() {
+        let text = card_data(10, &[("DL", US_CARD), ("ZV", "ZVAFOO\n")]);
+        let truncated = &text[..text.find("ZVZVA").unwrap()];
+        let record = parse(truncated).unwrap();
+        assert_eq!(record.subfile_types(), ["DL"]);
+        assert_eq!(record.validation_errors(), ["the ZV subfile is missing"]);
+
+        // cut inside the DL subfile, the elements before the cut are kept
+        let truncated = &text[..text.find("DBA").unwrap()];
+        let record = parse(truncated).unwrap();
+        assert_eq!(record.family_name().as_deref(), Some("SAMPLE"));
+        assert!(record
+            .validation_errors()
+            .contains(&"the expiration date (DBA) is missing".to_owned()));
+    }
+
+    #[test]
+    fn rejects_card_data_without_a_card_subfile() {
+        let text = card_data(10, &[("DL", US_CARD)]);
+        let header_only = &text[..text.find("DLDAQ").unwrap()];
+        let error = parse(header_only).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+        assert!(error.message().contains("no DL or ID subfile"));
+
+        let error = parse(&card_data(10, &[("ZV", "ZVAFOO\n")])).unwrap_err();
+        assert!(error.message().contains("no DL or ID subfile"));
+    }
+
+    #[test]
+    fn rejects_a_malformed_header() {
+        let text = card_data(10, &[("DL", US_CARD)]);
+        let error = parse(&text[1..]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+
+        let error = parse(&text[..text.find("DL0").unwrap() + 4]).unwrap_err();
+        assert_eq!(error.message(), "the header ends before the subfile offset");
+
+        let error = parse(&text.replacen("636014", "63601X", 1)).unwrap_err();
+        assert!(error.message().contains("issuer identification number"));
+
+        let error = parse(&card_data(11, &[("DL", US_CARD)])).unwrap_err();
+        assert!(error.message().contains("version 11 is not supported"));
+    }
+
+    #[test]
+    fn lists_what_is_wrong_with_a_readable_card() {
+        let data = US_CARD
+            .replace("DBC1\n", "DBCQ\n")
+            .replace("DBB07041990", "DBB13401990")
+            .replace("DAQD1234567\n", "DAQD1234567\nbad\n");
+        let text = card_data(10, &[("DL", &data)]).replacen('\u{1e}', "\u{1c}", 1);
+        let record = parse(&text).unwrap();
+        assert_eq!(
+            record.validation_errors(),
+            [
+                "the header separators after \"@\" are not line feed, record separator and \
+                 carriage return",
+                "the DL subfile has a malformed element \"bad\"",
+                "the date of birth (DBB) \"13401990\" is not a date",
+                "the sex (DBC) \"Q\" is not a known code",
+            ]
+        );
+    }
+}
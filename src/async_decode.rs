@@ -0,0 +1,291 @@
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+
+use rxing::common::HybridBinarizer;
+use rxing::multi::{GenericMultipleBarcodeReader, MultipleBarcodeReader};
+use rxing::{BinaryBitmap, DecodeHints, FilteredImageReader, LuminanceSource, RXingResult, Reader};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::binarizer::{AnyBinarizer, Binarizer, Binarizers};
+use crate::deadline::Deadline;
+use crate::decode_hints::DecodeHintDictionary;
+use crate::luma_source::SharedLumaSource;
+use crate::reader::{unrotate, DiagnosticReader, Failure, ONE_D_FORMATS};
+use crate::BarcodeResult;
+
+/// One step of an async decode. Control goes back to the event loop between steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    /// A search of the upright image without trying harder
+    Quick(Binarizer),
+    /// A search of the upright image trying harder
+    Thorough(Binarizer),
+    /// A search for linear barcodes whose bars run across the image, on a copy rotated a
+    /// quarter turn, trying harder
+    Rotated(Binarizer),
+    /// The whole search with rxing's `FilteredImageReader`, which can't be split
+    Filtered,
+}
+
+#[wasm_bindgen(unchecked_return_type = "Promise<BarcodeResult>")]
+/// Decode a barcode from an array of 8bit luma data without blocking the event loop.
+///
+/// Takes the same arguments as `decode_barcode_with_hints_and_binarizers`, but splits the search
+/// into passes and lets other tasks run between them. Each binarizer first gets a quick pass,
+/// then unless the `TryHarder` hint is "false" a thorough pass and a pass over the image rotated
+/// a quarter turn for linear barcodes. The promise resolves with the first barcode found, or rejects with
+/// the most specific error any pass gave. It rejects with a `Timeout` or `Aborted` error if the
+/// `max_duration_ms` budget or abort signal of the hints stops it first.
+pub fn decode_barcode_async(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    hints: &DecodeHintDictionary,
+    filter_image: Option<bool>,
+    binarizers: Option<Vec<Binarizer>>,
+) -> js_sys::Promise {
+    let deadline = Deadline::start(hints.limits());
+    let binarizers = hints.binarizers(binarizers.unwrap_or_default());
+    let hints = hints.get_dictionary().clone();
+    wasm_bindgen_futures::future_to_promise(async move {
+        let source = SharedLumaSource::new(data, width, height)?;
+        let mut failure = Failure::default();
+        let passes = passes(&hints, filter_image, &binarizers, false);
+        for (index, pass) in passes.into_iter().enumerate() {
+            if index > 0 {
+                yield_now().await?;
+            }
+            if deadline.passed() {
+                break;
+            }
+            if let ControlFlow::Break(result) =
+                failure.attempt(decode_pass(&source, pass, &hints, &binarizers, &deadline))
+            {
+                return Ok(BarcodeResult::from(result).into());
+            }
+        }
+        Err(deadline.error(failure.into_error()).into())
+    })
+}
+
+#[wasm_bindgen(unchecked_return_type = "Promise<BarcodeResult[]>")]
+/// Decode all barcodes in an array of 8bit luma data without blocking the event loop.
+///
+/// Takes the same arguments as `decode_multi_with_binarizers`. Like it, each binarizer is tried in turn until
+/// one finds any barcodes, and its thorough and rotated passes are run separately with other
+/// tasks let in between. If the budget or abort signal of the hints stops it, it resolves with
+/// the barcodes found so far, or rejects when there are none.
+pub fn decode_multi_async(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    hints: &DecodeHintDictionary,
+    filter_image: Option<bool>,
+    binarizers: Option<Vec<Binarizer>>,
+) -> js_sys::Promise {
+    let deadline = Deadline::start(hints.limits());
+    let binarizers = hints.binarizers(binarizers.unwrap_or_default());
+    let hints = hints.get_dictionary().clone();
+    wasm_bindgen_futures::future_to_promise(async move {
+        let source = SharedLumaSource::new(data, width, height)?;
+        let mut failure = Failure::default();
+        let mut found: Vec<RXingResult> = Vec::new();
+        let passes = passes(&hints, filter_image, &binarizers, true);
+        for (index, pass) in passes.iter().enumerate() {
+            if index > 0 {
+                yield_now().await?;
+            }
+            if deadline.passed() {
+                break;
+            }
+            if let ControlFlow::Break(results) = failure.attempt(decode_multi_pass(
+                &source,
+                *pass,
+                &hints,
+                &binarizers,
+                &deadline,
+            )) {
+                for result in results {
+                    let duplicate = found.iter().any(|seen| {
+                        seen.getText() == result.getText()
+                            && seen.getBarcodeFormat() == result.getBarcodeFormat()
+                    });
+                    if !duplicate {
+                        found.push(result);
+                    }
+                }
+            }
+            // the rotated pass belongs with the thorough pass of the same binarizer before it
+            let next = passes.get(index + 1);
+            if !found.is_empty() && !matches!(next, Some(Pass::Rotated(_))) {
+                break;
+            }
+        }
+        if found.is_empty() {
+            return Err(deadline.error(failure.into_error()).into());
+        }
+        let results: js_sys::Array = found
+            .into_iter()
+            .map(|result| JsValue::from(BarcodeResult::from(result)))
+            .collect();
+        Ok(results.into())
+    })
+}
+
+/// The passes to run in order. A single decode gives every binarizer a quick pass before any
+/// slower one, a multi decode finishes with each binarizer before moving to the next.
+fn passes(
+    hints: &DecodeHints,
+    filter_image: Option<bool>,
+    binarizers: &Binarizers,
+    multi: bool,
+) -> Vec<Pass> {
+    if matches!(filter_image, Some(true)) {
+        return vec![Pass::Filtered];
+    }
+    // like the sync functions, try harder unless told not to
+    let try_harder = hints.TryHarder != Some(false);
+    let rotated = try_harder && !one_d_formats(hints).is_empty();
+    let slow_passes = |binarizer: Binarizer| {
+        let mut passes = vec![Pass::Thorough(binarizer)];
+        if rotated {
+            passes.push(Pass::Rotated(binarizer));
+        }
+        passes
+    };
+    match (multi, try_harder) {
+        (_, false) => binarizers.iter().map(Pass::Quick).collect(),
+        (true, true) => binarizers.iter().flat_map(slow_passes).collect(),
+        (false, true) => {
+            let quick = binarizers.iter().map(Pass::Quick);
+            quick
+                .chain(binarizers.iter().flat_map(slow_passes))
+                .collect()
+        }
+    }
+}
+
+fn decode_pass(
+    source: &SharedLumaSource,
+    pass: Pass,
+    hints: &DecodeHints,
+    binarizers: &Binarizers,
+    deadline: &Deadline,
+) -> rxing::common::Result<RXingResult> {
+    let hints = pass_hints(pass, hints);
+    let mut reader = DiagnosticReader::new(&hints, deadline);
+    match pass {
+        Pass::Quick(binarizer) | Pass::Thorough(binarizer) => {
+            reader.decode_with_state(&mut upright_image(source, binarizers, binarizer))
+        }
+        Pass::Rotated(binarizer) => {
+            let result =
+                reader.decode_with_state(&mut rotated_image(source, binarizers, binarizer)?)?;
+            Ok(unrotate(result, source.get_width()))
+        }
+        Pass::Filtered => FilteredImageReader::new(reader).decode_with_hints(
+            &mut BinaryBitmap::new(HybridBinarizer::new(source.clone())),
+            &hints,
+        ),
+    }
+}
+
+fn decode_multi_pass(
+    source: &SharedLumaSource,
+    pass: Pass,
+    hints: &DecodeHints,
+    binarizers: &Binarizers,
+    deadline: &Deadline,
+) -> rxing::common::Result<Vec<RXingResult>> {
+    let hints = pass_hints(pass, hints);
+    let reader = DiagnosticReader::with_deadline(deadline);
+    match pass {
+        Pass::Quick(binarizer) | Pass::Thorough(binarizer) => GenericMultipleBarcodeReader::new(
+            reader,
+        )
+        .decode_multiple_with_hints(&mut upright_image(source, binarizers, binarizer), &hints),
+        Pass::Rotated(binarizer) => {
+            let results = GenericMultipleBarcodeReader::new(reader).decode_multiple_with_hints(
+                &mut rotated_image(source, binarizers, binarizer)?,
+                &hints,
+            )?;
+            Ok(results
+                .into_iter()
+                .map(|result| unrotate(result, source.get_width()))
+                .collect())
+        }
+        Pass::Filtered => GenericMultipleBarcodeReader::new(FilteredImageReader::new(reader))
+            .decode_multiple_with_hints(
+                &mut BinaryBitmap::new(HybridBinarizer::new(source.clone())),
+                &hints,
+            ),
+    }
+}
+
+/// The hints for one pass, with `TryHarder` set for it and the rotated pass limited to the
+/// linear formats
+fn pass_hints(pass: Pass, hints: &DecodeHints) -> DecodeHints {
+    let mut hints = hints.clone();
+    match pass {
+        Pass::Quick(_) => hints.TryHarder = Some(false),
+        Pass::Thorough(_) => hints.TryHarder = Some(true),
+        Pass::Rotated(_) => {
+            hints.TryHarder = Some(true);
+            hints.PossibleFormats = Some(one_d_formats(&hints));
+        }
+        Pass::Filtered => hints.TryHarder = hints.TryHarder.or(Some(true)),
+    }
+    hints
+}
+
+/// The linear formats the hints ask for, which are the only ones that care about rotation
+fn one_d_formats(hints: &DecodeHints) -> HashSet<rxing::BarcodeFormat> {
+    ONE_D_FORMATS
+        .into_iter()
+        .filter(|format| match &hints.PossibleFormats {
+            Some(formats) if !formats.is_empty() => formats.contains(format),
+            _ => true,
+        })
+        .collect()
+}
+
+/// The image as it is, which the readers may not rotate since a later pass does that
+fn upright_image(
+    source: &SharedLumaSource,
+    binarizers: &Binarizers,
+    binarizer: Binarizer,
+) -> BinaryBitmap<AnyBinarizer<SharedLumaSource>> {
+    binarizers.image(binarizer, source.clone().upright())
+}
+
+/// The image turned a quarter counter-clockwise, so vertical bars become horizontal
+fn rotated_image(
+    source: &SharedLumaSource,
+    binarizers: &Binarizers,
+    binarizer: Binarizer,
+) -> rxing::common::Result<BinaryBitmap<AnyBinarizer<SharedLumaSource>>> {
+    let rotated = source.clone().upright().rotate_counter_clockwise()?;
+    Ok(binarizers.image(binarizer, rotated))
+}
+
+/// Wait for a new task of the event loop, so rendering, input and I/O get a turn. A resolved
+/// promise would only queue a microtask, which runs before any of them.
+async fn yield_now() -> Result<(), JsValue> {
+    let global = js_sys::global();
+    let set_timeout = js_sys::Reflect::get(&global, &JsValue::from_str("setTimeout"))?;
+    let Some(set_timeout) = set_timeout.dyn_ref::<js_sys::Function>() else {
+        return Ok(());
+    };
+    let mut wait = |resolve: js_sys::Function, _reject: js_sys::Function| {
+        if set_timeout
+            .call2(&global, &resolve, &JsValue::from(0))
+            .is_err()
+        {
+            // carry on straight away rather than never
+            let _ = resolve.call0(&JsValue::UNDEFINED);
+        }
+    };
+    JsFuture::from(js_sys::Promise::new(&mut wait)).await?;
+    Ok(())
+}
@@ -0,0 +1,351 @@
+use std::collections::HashSet;
+
+use rxing::DecodeHints;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::binarizer::Binarizers;
+use crate::deadline::Deadline;
+use crate::error::{BarcodeError, ErrorKind};
+use crate::result_metadata::set;
+use crate::{geometry, BarcodeFormat, BarcodeResult};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_BARCODE_DETECTOR: &'static str = r#"
+/** The barcode format names of the Shape Detection API */
+export type DetectedBarcodeFormat =
+    | "aztec"
+    | "code_128"
+    | "code_39"
+    | "code_93"
+    | "codabar"
+    | "data_matrix"
+    | "ean_13"
+    | "ean_8"
+    | "itf"
+    | "pdf417"
+    | "qr_code"
+    | "upc_a"
+    | "upc_e"
+    | "unknown";
+
+export interface BarcodeDetectorOptions {
+    formats?: DetectedBarcodeFormat[];
+}
+
+/** A barcode found by `BarcodeDetector.detect`, in the shape of the Shape Detection API */
+export interface DetectedBarcode {
+    boundingBox: DOMRectReadOnly;
+    rawValue: string;
+    format: DetectedBarcodeFormat;
+    cornerPoints: { x: number; y: number }[];
+}
+"#;
+
+/// The formats of the Shape Detection API, all of which rxing reads, with their names there
+const FORMATS: [(&str, BarcodeFormat); 13] = [
+    ("aztec", BarcodeFormat::AZTEC),
+    ("code_128", BarcodeFormat::Code128),
+    ("code_39", BarcodeFormat::Code39),
+    ("code_93", BarcodeFormat::Code93),
+    ("codabar", BarcodeFormat::CODABAR),
+    ("data_matrix", BarcodeFormat::DataMatrix),
+    ("ean_13", BarcodeFormat::Ean13),
+    ("ean_8", BarcodeFormat::Ean8),
+    ("itf", BarcodeFormat::ITF),
+    ("pdf417", BarcodeFormat::Pdf417),
+    ("qr_code", BarcodeFormat::QrCode),
+    ("upc_a", BarcodeFormat::UpcA),
+    ("upc_e", BarcodeFormat::UpcE),
+];
+
+#[wasm_bindgen]
+/// A drop in for the `BarcodeDetector` of the W3C Shape Detection API, for browsers and runtimes
+/// that don't have one.
+///
+/// It only looks for the formats the API names, so results are the same shape as the native
+/// detector's. Use `decode_multi` directly for the other formats rxing reads.
+pub struct BarcodeDetector {
+    hints: DecodeHints,
+}
+
+#[wasm_bindgen]
+impl BarcodeDetector {
+    /// Create a detector for `options.formats`, or every supported format if it is left out.
+    ///
+    /// Throws a `TypeError`, as the native detector does, if `formats` is empty or has a name
+    /// that isn't a supported format, including "unknown".
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        #[wasm_bindgen(unchecked_param_type = "BarcodeDetectorOptions")] options: Option<
+            js_sys::Object,
+        >,
+    ) -> Result<BarcodeDetector, JsValue> {
+        let formats = match options {
+            Some(options) => js_sys::Reflect::get(&options, &JsValue::from_str("formats"))?,
+            None => JsValue::UNDEFINED,
+        };
+        let possible_formats = if formats.is_undefined() {
+            FORMATS.iter().map(|(_, format)| (*format).into()).collect()
+        } else {
+            let formats = formats
+                .dyn_into::<js_sys::Array>()
+                .map_err(|_| type_error("formats must be an array of barcode format names"))?;
+            if formats.length() == 0 {
+                return Err(type_error("formats must not be empty"));
+            }
+            formats
+                .iter()
+                .map(|name| {
+                    let name = name.as_string().unwrap_or_default();
+                    FORMATS
+                        .iter()
+                        .find(|(format_name, _)| *format_name == name)
+                        .map(|(_, format)| rxing::BarcodeFormat::from(*format))
+                        .ok_or_else(|| {
+                            type_error(&format!("\"{name}\" is not a supported barcode format"))
+                        })
+                })
+                .collect::<Result<HashSet<_>, _>>()?
+        };
+        Ok(BarcodeDetector {
+            hints: DecodeHints {
+                PossibleFormats: Some(possible_formats),
+                ..Default::default()
+            },
+        })
+    }
+
+    #[wasm_bindgen(
+        js_name = getSupportedFormats,
+        unchecked_return_type = "Promise<DetectedBarcodeFormat[]>"
+    )]
+    /// The names of the formats a detector can be created for
+    pub fn get_supported_formats() -> js_sys::Promise {
+        let names: js_sys::Array = FORMATS
+            .iter()
+            .map(|(name, _)| JsValue::from_str(name))
+            .collect();
+        js_sys::Promise::resolve(&JsValue::from(names))
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "Promise<DetectedBarcode[]>")]
+    /// Find every barcode in an image. Resolves to an empty array if there are none, or if the
+    /// image has no pixels yet, such as a video before its first frame.
+    ///
+    /// `ImageData`, and anything shaped like it with `data`, `width` and `height`, is read
+    /// directly, which is all that works outside a browser. Images, videos, canvases and image
+    /// bitmaps are drawn on an `OffscreenCanvas` to read them, and other sources such as a
+    /// `Blob` go through `createImageBitmap` first.
+    pub fn detect(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ImageBitmapSource")] image: JsValue,
+    ) -> js_sys::Promise {
+        let mut hints = self.hints.clone();
+        wasm_bindgen_futures::future_to_promise(async move {
+            let Some((luma, width, height)) = load_luma(image).await? else {
+                return Ok(js_sys::Array::new().into());
+            };
+            let results = match crate::detect_multiple_in_luma(
+                luma,
+                width,
+                height,
+                &mut hints,
+                &Deadline::default(),
+                None,
+                &Binarizers::default(),
+            ) {
+                Ok(results) => results,
+                Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(error) => return Err(error.into()),
+            };
+            let detected: js_sys::Array = results
+                .into_iter()
+                .map(|result| detected_barcode(&BarcodeResult::from(result)))
+                .collect();
+            Ok(detected.into())
+        })
+    }
+}
+
+/// The `DetectedBarcode` object for a result
+fn detected_barcode(result: &BarcodeResult) -> JsValue {
+    let corners = result.corners();
+    let object = js_sys::Object::new();
+    set(
+        &object,
+        "boundingBox",
+        bounding_rect(corners.as_ref().map_or(&[][..], |corners| &corners[..])),
+    );
+    set(&object, "rawValue", result.text.as_str().into());
+    let format = FORMATS
+        .iter()
+        .find(|(_, format)| *format == result.format)
+        .map_or("unknown", |(name, _)| name);
+    set(&object, "format", format.into());
+    let points: js_sys::Array = corners
+        .iter()
+        .flatten()
+        .map(|(x, y)| {
+            let point = js_sys::Object::new();
+            set(&point, "x", (*x).into());
+            set(&point, "y", (*y).into());
+            JsValue::from(point)
+        })
+        .collect();
+    set(&object, "cornerPoints", points.into());
+    object.into()
+}
+
+/// A `DOMRectReadOnly` around the corners, or a plain object with the same fields where there
+/// is no `DOMRectReadOnly`
+fn bounding_rect(corners: &[(f32, f32)]) -> JsValue {
+    let (left, top, right, bottom) = match corners {
+        [] => (0.0, 0.0, 0.0, 0.0),
+        corners => geometry::extent(corners),
+    };
+    let (width, height) = (right - left, bottom - top);
+    if let Ok(rect) = web_sys::DomRectReadOnly::new_with_x_and_y_and_width_and_height(
+        left.into(),
+        top.into(),
+        width.into(),
+        height.into(),
+    ) {
+        return rect.into();
+    }
+    let rect = js_sys::Object::new();
+    for (name, value) in [
+        ("x", left),
+        ("y", top),
+        ("width", width),
+        ("height", height),
+        ("top", top),
+        ("right", right),
+        ("bottom", bottom),
+        ("left", left),
+    ] {
+        set(&rect, name, value.into());
+    }
+    rect.into()
+}
+
+/// The luma pixels of an image source with its width and height, or `None` if it is empty
+async fn load_luma(image: JsValue) -> Result<Option<(Vec<u8>, u32, u32)>, JsValue> {
+    if let Some((rgba, width, height)) = image_data(&image)? {
+        return Ok((width > 0 && height > 0).then(|| (crate::rgba_to_luma(&rgba), width, height)));
+    }
+    let bitmap = match Drawable::of(&image) {
+        Some(_) => None,
+        None => Some(
+            JsFuture::from(create_image_bitmap(&image)?)
+                .await?
+                .dyn_into::<web_sys::ImageBitmap>()?,
+        ),
+    };
+    let drawable = match &bitmap {
+        Some(bitmap) => Drawable::Bitmap(bitmap),
+        None => Drawable::of(&image).ok_or_else(|| type_error("the image can't be drawn"))?,
+    };
+
+    let (width, height) = drawable.size();
+    let pixels = if width > 0 && height > 0 {
+        let canvas = web_sys::OffscreenCanvas::new(width, height)?;
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| BarcodeError::illegal_argument("no 2d context available"))?
+            .dyn_into::<web_sys::OffscreenCanvasRenderingContext2d>()?;
+        drawable.draw(&context)?;
+        let image_data = context.get_image_data(0.0, 0.0, width as f64, height as f64)?;
+        Some((crate::rgba_to_luma(&image_data.data()), width, height))
+    } else {
+        None
+    };
+    // free the decoded copy now rather than when it is garbage collected
+    if let Some(bitmap) = bitmap {
+        bitmap.close();
+    }
+    Ok(pixels)
+}
+
+/// The RGBA pixels of an `ImageData`, or of anything shaped like one since Node doesn't have it
+fn image_data(image: &JsValue) -> Result<Option<(Vec<u8>, u32, u32)>, BarcodeError> {
+    let property = |name: &str| js_sys::Reflect::get(image, &JsValue::from_str(name)).ok();
+    let Some(data) =
+        property("data").and_then(|data| data.dyn_into::<js_sys::Uint8ClampedArray>().ok())
+    else {
+        return Ok(None);
+    };
+    let dimension = |name: &str| property(name).and_then(|value| value.as_f64());
+    let (Some(width), Some(height)) = (dimension("width"), dimension("height")) else {
+        return Ok(None);
+    };
+    let (width, height) = (width as u32, height as u32);
+    if data.length() as u64 != width as u64 * height as u64 * 4 {
+        return Err(BarcodeError::invalid_input(format!(
+            "image data of {} bytes is not {width}x{height} RGBA pixels",
+            data.length()
+        )));
+    }
+    Ok(Some((data.to_vec(), width, height)))
+}
+
+/// `createImageBitmap(image)`, for the sources that can't be drawn on a canvas directly
+fn create_image_bitmap(image: &JsValue) -> Result<js_sys::Promise, JsValue> {
+    let global = js_sys::global();
+    let create = js_sys::Reflect::get(&global, &JsValue::from_str("createImageBitmap"))?
+        .dyn_into::<js_sys::Function>()
+        .map_err(|_| {
+            type_error("only ImageData can be read where there is no createImageBitmap")
+        })?;
+    create.call1(&global, image)?.dyn_into()
+}
+
+/// The image sources that can be drawn on a canvas as they are
+enum Drawable<'a> {
+    Image(&'a web_sys::HtmlImageElement),
+    Video(&'a web_sys::HtmlVideoElement),
+    Canvas(&'a web_sys::HtmlCanvasElement),
+    OffscreenCanvas(&'a web_sys::OffscreenCanvas),
+    Bitmap(&'a web_sys::ImageBitmap),
+}
+
+impl<'a> Drawable<'a> {
+    fn of(image: &'a JsValue) -> Option<Self> {
+        image
+            .dyn_ref()
+            .map(Drawable::Image)
+            .or_else(|| image.dyn_ref().map(Drawable::Video))
+            .or_else(|| image.dyn_ref().map(Drawable::Canvas))
+            .or_else(|| image.dyn_ref().map(Drawable::OffscreenCanvas))
+            .or_else(|| image.dyn_ref().map(Drawable::Bitmap))
+    }
+
+    /// The size of the pixels the source holds, 0 for an image or video that hasn't loaded
+    fn size(&self) -> (u32, u32) {
+        match self {
+            Drawable::Image(image) => (image.natural_width(), image.natural_height()),
+            Drawable::Video(video) => (video.video_width(), video.video_height()),
+            Drawable::Canvas(canvas) => (canvas.width(), canvas.height()),
+            Drawable::OffscreenCanvas(canvas) => (canvas.width(), canvas.height()),
+            Drawable::Bitmap(bitmap) => (bitmap.width(), bitmap.height()),
+        }
+    }
+
+    fn draw(&self, context: &web_sys::OffscreenCanvasRenderingContext2d) -> Result<(), JsValue> {
+        match self {
+            Drawable::Image(image) => context.draw_image_with_html_image_element(image, 0.0, 0.0),
+            Drawable::Video(video) => context.draw_image_with_html_video_element(video, 0.0, 0.0),
+            Drawable::Canvas(canvas) => {
+                context.draw_image_with_html_canvas_element(canvas, 0.0, 0.0)
+            }
+            Drawable::OffscreenCanvas(canvas) => {
+                context.draw_image_with_offscreen_canvas(canvas, 0.0, 0.0)
+            }
+            Drawable::Bitmap(bitmap) => context.draw_image_with_image_bitmap(bitmap, 0.0, 0.0),
+        }
+    }
+}
+
+fn type_error(message: &str) -> JsValue {
+    js_sys::TypeError::new(message).into()
+}
@@ -0,0 +1,456 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::ops::ControlFlow;
+
+use rxing::common::{
+    BitArray, BitMatrix, GlobalHistogramBinarizer, HybridBinarizer, LineOrientation, Result,
+};
+use rxing::{BinaryBitmap, LuminanceSource};
+use wasm_bindgen::prelude::*;
+
+use crate::reader::Failure;
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+/// Ways to turn a grayscale image into black and white before searching it for barcodes
+pub enum Binarizer {
+    /**
+     * A local threshold for each 8x8 block, smoothed over its neighbours. Handles shadows and
+     * gradients well. This is the default.
+     */
+    Hybrid,
+
+    /**
+     * A threshold picked from a histogram of the luminance, per row for 1D formats and once for
+     * the whole image for 2D formats. Fast, but easily thrown off by uneven lighting.
+     */
+    GlobalHistogram,
+
+    /**
+     * Each pixel is compared with the mean of a window around it, a side of one eighth of the
+     * image. Pixels 5% darker than their surroundings are black. Suits faint print and uneven
+     * lighting.
+     */
+    LocalMean,
+
+    /**
+     * Pixels darker than a fixed level are black. The level is mid gray (128) unless the hints
+     * set another with `set_fixed_threshold`.
+     */
+    FixedThreshold,
+
+    /**
+     * One threshold for the whole image, chosen with Otsu's method to best separate the dark and
+     * light pixels. Suits low contrast labels on an even background.
+     */
+    Otsu,
+}
+
+/// The level below which `Binarizer::FixedThreshold` makes a pixel black, unless the hints set one
+pub(crate) const DEFAULT_FIXED_THRESHOLD: u8 = 128;
+
+/// The binarizers to try in order, along with the settings they share
+#[derive(Debug, Clone)]
+pub(crate) struct Binarizers {
+    list: Vec<Binarizer>,
+    fixed_threshold: u8,
+}
+
+impl Default for Binarizers {
+    fn default() -> Self {
+        Self::new(Vec::new(), DEFAULT_FIXED_THRESHOLD)
+    }
+}
+
+impl Binarizers {
+    /// An empty list uses `Hybrid`
+    pub(crate) fn new(list: Vec<Binarizer>, fixed_threshold: u8) -> Self {
+        Self {
+            list,
+            fixed_threshold,
+        }
+    }
+
+    #[cfg(feature = "decode_hints")]
+    pub(crate) fn set_list(&mut self, list: Vec<Binarizer>) {
+        self.list = list;
+    }
+
+    #[cfg(feature = "decode_hints")]
+    pub(crate) fn set_fixed_threshold(&mut self, fixed_threshold: u8) {
+        self.fixed_threshold = fixed_threshold;
+    }
+
+    #[cfg(feature = "decode_hints")]
+    /// The binarizers in the order they are tried
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Binarizer> + '_ {
+        let list = if self.list.is_empty() {
+            &[Binarizer::Hybrid][..]
+        } else {
+            &self.list
+        };
+        list.iter().copied()
+    }
+
+    /// An image of `source` binarized with `binarizer`
+    pub(crate) fn image<LS: LuminanceSource>(
+        &self,
+        binarizer: Binarizer,
+        source: LS,
+    ) -> BinaryBitmap<AnyBinarizer<LS>> {
+        BinaryBitmap::new(AnyBinarizer::new(binarizer, self.fixed_threshold, source))
+    }
+}
+
+/// Decode `source` with each of `binarizers` in turn and return the first success, or the most
+/// specific error any of them gave.
+pub(crate) fn decode_with_binarizers<LS, T>(
+    source: LS,
+    binarizers: &Binarizers,
+    mut decode: impl FnMut(&mut BinaryBitmap<AnyBinarizer<LS>>) -> Result<T>,
+) -> Result<T>
+where
+    LS: LuminanceSource + Clone,
+{
+    let Some((last, first)) = binarizers.list.split_last() else {
+        return decode(&mut binarizers.image(Binarizer::Hybrid, source));
+    };
+    let mut failure = Failure::default();
+    for binarizer in first {
+        let mut image = binarizers.image(*binarizer, source.clone());
+        if let ControlFlow::Break(result) = failure.attempt(decode(&mut image)) {
+            return Ok(result);
+        }
+    }
+    let mut image = binarizers.image(*last, source);
+    match failure.attempt(decode(&mut image)) {
+        ControlFlow::Break(result) => Ok(result),
+        ControlFlow::Continue(()) => Err(failure.into_error()),
+    }
+}
+
+/// One of the `Binarizer` strategies, so every strategy can share a `BinaryBitmap` type
+pub(crate) enum AnyBinarizer<LS: LuminanceSource> {
+    Hybrid(HybridBinarizer<LS>),
+    GlobalHistogram(GlobalHistogramBinarizer<LS>),
+    Threshold(ThresholdBinarizer<LS>),
+}
+
+impl<LS: LuminanceSource> AnyBinarizer<LS> {
+    pub(crate) fn new(binarizer: Binarizer, fixed_threshold: u8, source: LS) -> Self {
+        match binarizer {
+            Binarizer::Hybrid => AnyBinarizer::Hybrid(HybridBinarizer::new(source)),
+            Binarizer::GlobalHistogram => {
+                AnyBinarizer::GlobalHistogram(GlobalHistogramBinarizer::new(source))
+            }
+            Binarizer::LocalMean => {
+                AnyBinarizer::Threshold(ThresholdBinarizer::new(source, Threshold::LocalMean))
+            }
+            Binarizer::FixedThreshold => AnyBinarizer::Threshold(ThresholdBinarizer::new(
+                source,
+                Threshold::Fixed(fixed_threshold),
+            )),
+            Binarizer::Otsu => {
+                AnyBinarizer::Threshold(ThresholdBinarizer::new(source, Threshold::Otsu))
+            }
+        }
+    }
+}
+
+impl<LS: LuminanceSource> rxing::Binarizer for AnyBinarizer<LS> {
+    type Source = LS;
+
+    fn get_luminance_source(&self) -> &LS {
+        match self {
+            AnyBinarizer::Hybrid(binarizer) => binarizer.get_luminance_source(),
+            AnyBinarizer::GlobalHistogram(binarizer) => binarizer.get_luminance_source(),
+            AnyBinarizer::Threshold(binarizer) => binarizer.get_luminance_source(),
+        }
+    }
+
+    fn get_black_row(&self, y: usize) -> Result<Cow<'_, BitArray>> {
+        match self {
+            AnyBinarizer::Hybrid(binarizer) => binarizer.get_black_row(y),
+            AnyBinarizer::GlobalHistogram(binarizer) => binarizer.get_black_row(y),
+            AnyBinarizer::Threshold(binarizer) => binarizer.get_black_row(y),
+        }
+    }
+
+    fn get_black_row_from_matrix(&self, y: usize) -> Result<Cow<'_, BitArray>> {
+        match self {
+            AnyBinarizer::Hybrid(binarizer) => binarizer.get_black_row_from_matrix(y),
+            AnyBinarizer::GlobalHistogram(binarizer) => binarizer.get_black_row_from_matrix(y),
+            AnyBinarizer::Threshold(binarizer) => binarizer.get_black_row_from_matrix(y),
+        }
+    }
+
+    fn get_black_matrix(&self) -> Result<&BitMatrix> {
+        match self {
+            AnyBinarizer::Hybrid(binarizer) => binarizer.get_black_matrix(),
+            AnyBinarizer::GlobalHistogram(binarizer) => binarizer.get_black_matrix(),
+            AnyBinarizer::Threshold(binarizer) => binarizer.get_black_matrix(),
+        }
+    }
+
+    fn get_black_line(&self, l: usize, lt: LineOrientation) -> Result<Cow<'_, BitArray>> {
+        match self {
+            AnyBinarizer::Hybrid(binarizer) => binarizer.get_black_line(l, lt),
+            AnyBinarizer::GlobalHistogram(binarizer) => binarizer.get_black_line(l, lt),
+            AnyBinarizer::Threshold(binarizer) => binarizer.get_black_line(l, lt),
+        }
+    }
+
+    fn create_binarizer(&self, source: LS) -> Self {
+        match self {
+            AnyBinarizer::Hybrid(binarizer) => {
+                AnyBinarizer::Hybrid(binarizer.create_binarizer(source))
+            }
+            AnyBinarizer::GlobalHistogram(binarizer) => {
+                AnyBinarizer::GlobalHistogram(binarizer.create_binarizer(source))
+            }
+            AnyBinarizer::Threshold(binarizer) => {
+                AnyBinarizer::Threshold(binarizer.create_binarizer(source))
+            }
+        }
+    }
+
+    fn get_width(&self) -> usize {
+        self.get_luminance_source().get_width()
+    }
+
+    fn get_height(&self) -> usize {
+        self.get_luminance_source().get_height()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Threshold {
+    Fixed(u8),
+    Otsu,
+    LocalMean,
+}
+
+/// Binarizes the whole image at once by comparing every pixel with a threshold. Rows and
+/// columns are read from the finished matrix.
+pub(crate) struct ThresholdBinarizer<LS: LuminanceSource> {
+    source: LS,
+    threshold: Threshold,
+    matrix: OnceCell<BitMatrix>,
+}
+
+impl<LS: LuminanceSource> ThresholdBinarizer<LS> {
+    fn new(source: LS, threshold: Threshold) -> Self {
+        Self {
+            source,
+            threshold,
+            matrix: OnceCell::new(),
+        }
+    }
+
+    fn matrix(&self) -> Result<&BitMatrix> {
+        if let Some(matrix) = self.matrix.get() {
+            return Ok(matrix);
+        }
+        let matrix = self.build_matrix()?;
+        Ok(self.matrix.get_or_init(|| matrix))
+    }
+
+    fn build_matrix(&self) -> Result<BitMatrix> {
+        let width = self.source.get_width();
+        let height = self.source.get_height();
+        let luma = self.source.get_matrix();
+        let mut matrix = BitMatrix::new(width as u32, height as u32)?;
+
+        match self.threshold {
+            Threshold::Fixed(level) => set_darker(&mut matrix, &luma, width, |_, _| level as u32),
+            Threshold::Otsu => {
+                let level = otsu_level(&luma) as u32 + 1;
+                set_darker(&mut matrix, &luma, width, |_, _| level)
+            }
+            Threshold::LocalMean => {
+                let sums = integral_image(&luma, width, height);
+                let half = (width.max(height) / 16).max(4);
+                set_darker(&mut matrix, &luma, width, |x, y| {
+                    let (left, right) = (x.saturating_sub(half), (x + half + 1).min(width));
+                    let (top, bottom) = (y.saturating_sub(half), (y + half + 1).min(height));
+                    let stride = width + 1;
+                    let sum = sums[bottom * stride + right] + sums[top * stride + left]
+                        - sums[top * stride + right]
+                        - sums[bottom * stride + left];
+                    let count = ((right - left) * (bottom - top)) as u64;
+                    // black when at least 5% darker than the mean of the window
+                    (sum * 95).div_ceil(count * 100) as u32
+                })
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+/// Set every module whose luma is below the threshold returned for its position
+fn set_darker(
+    matrix: &mut BitMatrix,
+    luma: &[u8],
+    width: usize,
+    threshold: impl Fn(usize, usize) -> u32,
+) {
+    if width == 0 {
+        return;
+    }
+    for (y, row) in luma.chunks_exact(width).enumerate() {
+        for (x, &value) in row.iter().enumerate() {
+            if (value as u32) < threshold(x, y) {
+                matrix.set(x as u32, y as u32);
+            }
+        }
+    }
+}
+
+/// The luma value that best splits the pixels into a dark and a light class, which is the
+/// brightest value of the dark class
+fn otsu_level(luma: &[u8]) -> u8 {
+    let mut histogram = [0u64; 256];
+    for &value in luma {
+        histogram[value as usize] += 1;
+    }
+    let total = luma.len() as f64;
+    let total_sum: f64 = (0..256)
+        .map(|value| value as f64 * histogram[value] as f64)
+        .sum();
+
+    let mut best_level = 0;
+    let mut best_variance = 0.0;
+    let mut dark_count = 0.0;
+    let mut dark_sum = 0.0;
+    for (level, &count) in histogram.iter().enumerate() {
+        dark_count += count as f64;
+        dark_sum += (level as u64 * count) as f64;
+        let light_count = total - dark_count;
+        if dark_count == 0.0 || light_count == 0.0 {
+            continue;
+        }
+        let mean_difference = dark_sum / dark_count - (total_sum - dark_sum) / light_count;
+        let variance = dark_count * light_count * mean_difference * mean_difference;
+        if variance > best_variance {
+            best_variance = variance;
+            best_level = level;
+        }
+    }
+    best_level as u8
+}
+
+/// Summed area table with a zero row and column in front, so the sum of the rectangle
+/// `[left, right) x [top, bottom)` is `s[bottom][right] + s[top][left] - s[top][right] - s[bottom][left]`
+fn integral_image(luma: &[u8], width: usize, height: usize) -> Vec<u64> {
+    let stride = width + 1;
+    let mut sums = vec![0u64; stride * (height + 1)];
+    for y in 0..height {
+        let mut row_sum = 0;
+        for x in 0..width {
+            row_sum += luma[y * width + x] as u64;
+            sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+        }
+    }
+    sums
+}
+
+impl<LS: LuminanceSource> rxing::Binarizer for ThresholdBinarizer<LS> {
+    type Source = LS;
+
+    fn get_luminance_source(&self) -> &LS {
+        &self.source
+    }
+
+    fn get_black_row(&self, y: usize) -> Result<Cow<'_, BitArray>> {
+        Ok(Cow::Owned(self.matrix()?.getRow(y as u32)))
+    }
+
+    fn get_black_row_from_matrix(&self, y: usize) -> Result<Cow<'_, BitArray>> {
+        self.get_black_row(y)
+    }
+
+    fn get_black_matrix(&self) -> Result<&BitMatrix> {
+        self.matrix()
+    }
+
+    fn get_black_line(&self, l: usize, lt: LineOrientation) -> Result<Cow<'_, BitArray>> {
+        match lt {
+            LineOrientation::Row => self.get_black_row(l),
+            LineOrientation::Column => Ok(Cow::Owned(self.matrix()?.getCol(l as u32))),
+        }
+    }
+
+    fn create_binarizer(&self, source: LS) -> Self {
+        Self::new(source, self.threshold)
+    }
+
+    fn get_width(&self) -> usize {
+        self.source.get_width()
+    }
+
+    fn get_height(&self) -> usize {
+        self.source.get_height()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rxing::{Exceptions, Luma8LuminanceSource};
+
+    #[test]
+    fn the_most_specific_error_of_any_binarizer_is_kept() {
+        let source = Luma8LuminanceSource::new(vec![255; 16], 4, 4);
+        let mut errors = vec![
+            Exceptions::NOT_FOUND,
+            Exceptions::CHECKSUM,
+            Exceptions::FORMAT,
+        ];
+        let error = decode_with_binarizers::<_, ()>(
+            source,
+            &Binarizers::new(
+                vec![Binarizer::Hybrid, Binarizer::Otsu, Binarizer::LocalMean],
+                DEFAULT_FIXED_THRESHOLD,
+            ),
+            |_| Err(errors.remove(0)),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Exceptions::ChecksumException(_)));
+    }
+
+    #[test]
+    fn the_first_success_is_returned() {
+        let source = Luma8LuminanceSource::new(vec![255; 16], 4, 4);
+        let mut tried = 0;
+        let result = decode_with_binarizers(
+            source,
+            &Binarizers::new(
+                vec![Binarizer::Hybrid, Binarizer::Otsu, Binarizer::LocalMean],
+                DEFAULT_FIXED_THRESHOLD,
+            ),
+            |_| {
+                tried += 1;
+                if tried == 2 {
+                    Ok(tried)
+                } else {
+                    Err(Exceptions::CHECKSUM)
+                }
+            },
+        );
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[test]
+    fn the_fixed_threshold_is_configurable() {
+        let source = Luma8LuminanceSource::new(vec![100; 16], 4, 4);
+        let black = |threshold| {
+            let image = Binarizers::new(Vec::new(), threshold)
+                .image(Binarizer::FixedThreshold, source.clone());
+            let matrix = image.get_black_matrix();
+            (0..4).all(|y| (0..4).all(|x| matrix.get(x, y)))
+        };
+        assert!(black(DEFAULT_FIXED_THRESHOLD));
+        assert!(black(101));
+        assert!(!black(100));
+    }
+}
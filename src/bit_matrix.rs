@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+use crate::error::BarcodeError;
+#[cfg(feature = "png_output")]
+use crate::render::PngColorType;
+use crate::render::{self, RenderOptions, RgbaImage};
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The modules of an encoded barcode. x is the column and y is the row, with the origin at the
+/// top-left. A set module is a dark bar or square.
+pub struct BitMatrix(rxing::common::BitMatrix);
+
+#[wasm_bindgen]
+impl BitMatrix {
+    pub fn width(&self) -> u32 {
+        self.0.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.0.height()
+    }
+
+    /// Whether the module at (x, y) is set. Positions outside the matrix are never set.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.0.try_get(x, y).unwrap_or(false)
+    }
+
+    /// One byte per module for row `y`, 1 for a set module and 0 otherwise.
+    /// Rows outside the matrix are empty.
+    pub fn row(&self, y: u32) -> Vec<u8> {
+        if y >= self.0.height() {
+            return Vec::new();
+        }
+        (0..self.0.width())
+            .map(|x| u8::from(self.0.get(x, y)))
+            .collect()
+    }
+
+    /// All modules packed eight to a byte, most significant bit first. Every row starts on a new
+    /// byte, so each row takes `Math.ceil(width / 8)` bytes.
+    pub fn to_packed(&self) -> Vec<u8> {
+        let row_bytes = (self.0.width() as usize).div_ceil(8);
+        let mut packed = vec![0u8; row_bytes * self.0.height() as usize];
+        for y in 0..self.0.height() {
+            for x in 0..self.0.width() {
+                if self.0.get(x, y) {
+                    packed[y as usize * row_bytes + x as usize / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+        packed
+    }
+
+    /// Whether both matrices have the same size and the same modules set
+    pub fn equals(&self, other: &BitMatrix) -> bool {
+        self == other
+    }
+
+    /// The text grid produced by `encode_barcode`, "X" for set modules and " " otherwise
+    pub fn to_text(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn to_svg(&self, options: &RenderOptions) -> String {
+        render::bit_matrix_to_svg(&self.0, options)
+    }
+
+    pub fn to_rgba(&self, options: &RenderOptions) -> Result<RgbaImage, BarcodeError> {
+        render::bit_matrix_to_rgba(&self.0, options)
+    }
+
+    pub fn to_image_data(&self, options: &RenderOptions) -> Result<web_sys::ImageData, JsValue> {
+        render::bit_matrix_to_rgba(&self.0, options)?.into_image_data()
+    }
+
+    #[cfg(feature = "png_output")]
+    pub fn to_png(
+        &self,
+        options: &RenderOptions,
+        color_type: PngColorType,
+    ) -> Result<Vec<u8>, BarcodeError> {
+        render::bit_matrix_to_png(&self.0, options, color_type)
+    }
+}
+
+impl From<rxing::common::BitMatrix> for BitMatrix {
+    fn from(value: rxing::common::BitMatrix) -> Self {
+        Self(value)
+    }
+}
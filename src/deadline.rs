@@ -0,0 +1,125 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use rxing::common::Result;
+use rxing::Exceptions;
+use wasm_bindgen::prelude::*;
+
+use crate::error::{BarcodeError, ErrorKind};
+
+/// How long a decode may run and what can cancel it
+#[derive(Clone, Default)]
+pub(crate) struct DecodeLimits {
+    pub(crate) max_duration_ms: Option<f64>,
+    /// An `AbortSignal`, or any object with an `aborted` property
+    pub(crate) signal: Option<JsValue>,
+}
+
+/// Why a decode stopped before running every reader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    TimedOut,
+    Aborted,
+}
+
+/// The point at which a decode gives up, checked before each reader runs and between the rows
+/// and rotations the 1D readers scan.
+///
+/// Clones share their state, so readers wrapped inside rxing's multi and filtered readers see the
+/// same clock as the code that started the decode, and a decoder can restart it for each frame.
+/// Any other reader that has already started runs to the end, so a decode can overrun its budget
+/// by the time of the slowest 2D reader.
+#[derive(Clone, Default)]
+pub(crate) struct Deadline(Rc<State>);
+
+#[derive(Default)]
+struct State {
+    limits: RefCell<DecodeLimits>,
+    expires_at: Cell<Option<f64>>,
+    stop: Cell<Option<Stop>>,
+}
+
+impl Deadline {
+    /// A deadline for `limits` whose clock starts now
+    pub(crate) fn start(limits: &DecodeLimits) -> Self {
+        let deadline = Deadline::default();
+        deadline.restart(limits);
+        deadline
+    }
+
+    /// Use `limits` and start the clock again
+    pub(crate) fn restart(&self, limits: &DecodeLimits) {
+        self.0.limits.replace(limits.clone());
+        self.0.expires_at.set(
+            limits
+                .max_duration_ms
+                .map(|duration| js_sys::Date::now() + duration),
+        );
+        self.0.stop.set(None);
+    }
+
+    /// Whether the budget has run out or the signal has been aborted. Once passed, the deadline
+    /// stays passed until it is restarted.
+    pub(crate) fn passed(&self) -> bool {
+        if self.0.stop.get().is_some() {
+            return true;
+        }
+        let stop = if self.aborted() {
+            Some(Stop::Aborted)
+        } else if self
+            .0
+            .expires_at
+            .get()
+            .is_some_and(|expires_at| js_sys::Date::now() >= expires_at)
+        {
+            Some(Stop::TimedOut)
+        } else {
+            None
+        };
+        self.0.stop.set(stop);
+        stop.is_some()
+    }
+
+    fn aborted(&self) -> bool {
+        let limits = self.0.limits.borrow();
+        let Some(signal) = &limits.signal else {
+            return false;
+        };
+        js_sys::Reflect::get(signal, &JsValue::from_str("aborted"))
+            .is_ok_and(|aborted| aborted.is_truthy())
+    }
+
+    /// Fail if the deadline has passed
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.passed() {
+            return Err(Exceptions::not_found_with("the decode was stopped early"));
+        }
+        Ok(())
+    }
+
+    /// Run `decode` unless the deadline has passed, in which case fail without starting it
+    pub(crate) fn run<T>(&self, decode: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.check()?;
+        decode()
+    }
+
+    /// The error for a decode that failed with `error`. If the deadline cut the decode short this
+    /// is a `Timeout` or `Aborted` error, which mentions `error` when a reader got further than
+    /// not finding anything.
+    pub(crate) fn error(&self, error: Exceptions) -> BarcodeError {
+        match self.0.stop.get() {
+            None => error.into(),
+            Some(Stop::Aborted) => BarcodeError::new(ErrorKind::Aborted, "the decode was aborted"),
+            Some(Stop::TimedOut) => {
+                let budget = self.0.limits.borrow().max_duration_ms.unwrap_or_default();
+                let message = match error {
+                    Exceptions::NotFoundException(_) => {
+                        format!("no barcode was found within {budget} ms")
+                    }
+                    error => format!("no barcode was read within {budget} ms, closest: {error}"),
+                };
+                BarcodeError::new(ErrorKind::Timeout, message)
+            }
+        }
+    }
+}
@@ -1,7 +1,14 @@
-use rxing::DecodeHints;
+use rxing::{DecodeHints, Point, PointCallback};
 use std::collections::HashSet;
+use std::mem::ManuallyDrop;
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
 use wasm_bindgen::prelude::*;
 
+use crate::binarizer::{Binarizer, Binarizers, DEFAULT_FIXED_THRESHOLD};
+use crate::deadline::DecodeLimits;
+use crate::error::BarcodeError;
+
 #[wasm_bindgen]
 pub enum DecodeHintTypes {
     /**
@@ -59,7 +66,7 @@ pub enum DecodeHintTypes {
 
     /**
      * The caller needs to be notified via callback when a possible {@link RXingResultPoint}
-     * is found. Maps to a {@link RXingResultPointCallback}.
+     * is found. Set with `set_result_point_callback`, `set_hint` does not accept it.
      */
     NeedResultPointCallback,
 
@@ -112,13 +119,13 @@ impl From<DecodeHintTypes> for rxing::DecodeHintType {
 
 #[wasm_bindgen]
 #[derive(Default, Clone)]
-pub struct DecodeHintDictionary(DecodeHints);
+pub struct DecodeHintDictionary(DecodeHints, DecodeLimits, Option<u8>);
 
 #[wasm_bindgen]
 impl DecodeHintDictionary {
     #[wasm_bindgen(constructor)]
     pub fn new() -> DecodeHintDictionary {
-        DecodeHintDictionary(DecodeHints::default())
+        DecodeHintDictionary::default()
     }
 
     #[wasm_bindgen]
@@ -188,7 +195,7 @@ impl DecodeHintDictionary {
                 .0
                 .NeedResultPointCallback
                 .as_ref()
-                .map(|_| String::from("UNSUPORTED NeedResultPointCallback"))
+                .map(|_| String::from("function"))
                 .unwrap_or_default(),
             DecodeHintTypes::AllowedEanExtensions => self
                 .0
@@ -297,6 +304,7 @@ impl DecodeHintDictionary {
                 self.0.ReturnCodabarStartEnd = Some(return_codebar_start_end);
             }
             DecodeHintTypes::NeedResultPointCallback => {
+                // needs a function, see `set_result_point_callback`
                 return false;
             }
             DecodeHintTypes::AllowedEanExtensions => {
@@ -340,21 +348,80 @@ impl DecodeHintDictionary {
         true
     }
 
-    // #[wasm_bindgen]
-    // pub fn setResultPointCallback(&mut self, callback: &js_sys::Function){
-    //     self.0.insert(rxing::DecodeHintType::NEED_RESULT_POINT_CALLBACK, rxing::DecodeHintValue::NeedResultPointCallback(
-    //         Rc::new(|point: &dyn rxing::ResultPoint|  {
-    //             let this = JsValue::null();
-    //             let js_point = wasm_bindgen::JsValue::from( js_sys::Array::from_iter([wasm_bindgen::JsValue::from(point.getX()), wasm_bindgen::JsValue::from(point.getY())].iter()) );
-    //             callback.call1(&this, &js_point);
-    //         })
-    //     ));
-    // }
-
-    // #[wasm_bindgen]
-    // pub fn clearResultPointCallback(&mut self) -> bool {
-    //     self.0.remove(&rxing::DecodeHintType::NEED_RESULT_POINT_CALLBACK).is_some()
-    // }
+    #[wasm_bindgen]
+    /// Call `callback` with the `x` and `y` of each candidate point a reader finds, such as QR
+    /// finder and alignment patterns, before the barcode is decoded. Anything the callback
+    /// throws is ignored. In a build with wasm threads, points found on a thread other than the
+    /// one that set the callback are not reported.
+    pub fn set_result_point_callback(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "(x: number, y: number) => void")]
+        callback: js_sys::Function,
+    ) {
+        let callback = JsPointCallback::new(callback);
+        self.0.NeedResultPointCallback = Some(Arc::new(move |point: Point| {
+            callback.call(point.x, point.y);
+        }));
+    }
+
+    #[wasm_bindgen]
+    /// Remove the callback set with `set_result_point_callback`, returns whether there was one
+    pub fn clear_result_point_callback(&mut self) -> bool {
+        self.0.NeedResultPointCallback.take().is_some()
+    }
+
+    #[wasm_bindgen]
+    /// Give up on a decode once it has run for `milliseconds`, leave it unset for no limit.
+    ///
+    /// The readers that haven't started by then are skipped, and the 1D readers stop at the next
+    /// row they scan. A 2D reader that has started runs to the end, so a decode can overrun by
+    /// the time of one 2D reader. A single decode that runs out of time throws a `Timeout` error, a multi
+    /// decode returns the barcodes found so far if there are any.
+    pub fn set_max_duration_ms(&mut self, milliseconds: Option<f64>) -> Result<(), BarcodeError> {
+        if milliseconds.is_some_and(|milliseconds| !milliseconds.is_finite() || milliseconds < 0.0)
+        {
+            return Err(BarcodeError::illegal_argument(
+                "max_duration_ms must be a finite number of milliseconds, 0 or more",
+            ));
+        }
+        self.1.max_duration_ms = milliseconds;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// The limit set with `set_max_duration_ms`, if any
+    pub fn max_duration_ms(&self) -> Option<f64> {
+        self.1.max_duration_ms
+    }
+
+    #[wasm_bindgen]
+    /// Stop decoding once `signal` is aborted, leave it unset to remove the signal. Any object
+    /// with an `aborted` property can be used in place of an `AbortSignal`.
+    ///
+    /// The signal is checked before each reader runs. The async decode functions let other code
+    /// run between passes, so they can be aborted part way through and reject with an `Aborted`
+    /// error. The other functions run to the end once called, and only notice a signal that was
+    /// aborted before they started.
+    pub fn set_abort_signal(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "AbortSignal | { readonly aborted: boolean }")]
+        signal: Option<JsValue>,
+    ) {
+        self.1.signal = signal.filter(|signal| !signal.is_null() && !signal.is_undefined());
+    }
+
+    #[wasm_bindgen]
+    /// Make `Binarizer.FixedThreshold` treat pixels darker than `threshold` as black, leave it
+    /// unset for mid gray (128).
+    pub fn set_fixed_threshold(&mut self, threshold: Option<u8>) {
+        self.2 = threshold;
+    }
+
+    #[wasm_bindgen]
+    /// The level `Binarizer.FixedThreshold` uses with these hints
+    pub fn fixed_threshold(&self) -> u8 {
+        self.2.unwrap_or(DEFAULT_FIXED_THRESHOLD)
+    }
 }
 
 impl DecodeHintDictionary {
@@ -364,4 +431,77 @@ impl DecodeHintDictionary {
     pub fn get_dictionary_mut(&mut self) -> &mut DecodeHints {
         &mut self.0
     }
+
+    /// The time budget and abort signal set on these hints
+    pub(crate) fn limits(&self) -> &DecodeLimits {
+        &self.1
+    }
+
+    /// `list` set up with the binarizer settings of these hints
+    pub(crate) fn binarizers(&self, list: Vec<Binarizer>) -> Binarizers {
+        Binarizers::new(list, self.fixed_threshold())
+    }
+
+    /// A copy of these hints whose point callback reports points moved by `x` and `y`, for
+    /// searching part of an image while reporting points in the whole image
+    pub(crate) fn translated(&self, x: f32, y: f32) -> DecodeHintDictionary {
+        let mut hints = self.clone();
+        if let Some(callback) = self.0.NeedResultPointCallback.clone() {
+            let translated: PointCallback = Arc::new(move |point: Point| {
+                callback(Point {
+                    x: point.x + x,
+                    y: point.y + y,
+                })
+            });
+            hints.0.NeedResultPointCallback = Some(translated);
+        }
+        hints
+    }
+}
+
+/// A javascript function called with each point rxing reports. Javascript values belong to the
+/// thread that created them, so the function is only touched on that thread.
+struct JsPointCallback {
+    function: ManuallyDrop<js_sys::Function>,
+    thread: ThreadId,
 }
+
+impl JsPointCallback {
+    fn new(function: js_sys::Function) -> Self {
+        Self {
+            function: ManuallyDrop::new(function),
+            thread: thread::current().id(),
+        }
+    }
+
+    fn on_own_thread(&self) -> bool {
+        thread::current().id() == self.thread
+    }
+
+    fn call(&self, x: f32, y: f32) {
+        if !self.on_own_thread() {
+            return;
+        }
+        // there is nowhere to report an exception from the callback to
+        let _ = self
+            .function
+            .call2(&JsValue::NULL, &JsValue::from(x), &JsValue::from(y));
+    }
+}
+
+impl Drop for JsPointCallback {
+    fn drop(&mut self) {
+        // releasing the function elsewhere would free a slot in another thread's table, so it is
+        // leaked instead
+        if self.on_own_thread() {
+            // SAFETY: the function is not used again after this
+            unsafe { ManuallyDrop::drop(&mut self.function) };
+        }
+    }
+}
+
+// SAFETY: rxing needs callbacks that can be shared between threads, but the function is only
+// called or dropped on the thread that created it, which `call` and `drop` check. Without wasm
+// threads (`target_feature = "atomics"`) that is the only thread there is.
+unsafe impl Send for JsPointCallback {}
+unsafe impl Sync for JsPointCallback {}
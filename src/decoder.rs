@@ -0,0 +1,170 @@
+use rxing::common::HybridBinarizer;
+use rxing::multi::{GenericMultipleBarcodeReader, MultipleBarcodeReader};
+use rxing::{BinaryBitmap, DecodeHints, FilteredImageReader, Reader};
+use wasm_bindgen::prelude::*;
+
+use crate::binarizer::{self, Binarizer, Binarizers};
+use crate::deadline::{Deadline, DecodeLimits};
+use crate::decode_hints::DecodeHintDictionary;
+use crate::error::BarcodeError;
+use crate::luma_source::SharedLumaSource;
+use crate::pixel_format::{self, PixelFormat};
+use crate::reader::DiagnosticReader;
+use crate::BarcodeResult;
+
+#[wasm_bindgen]
+/// A configured barcode reader that can be used for many frames.
+///
+/// The readers, hints and luma buffer are set up once and reused by every call, which avoids the
+/// allocation and setup the free `decode_*` functions repeat each time. Use one per camera stream.
+pub struct Decoder {
+    hints: DecodeHints,
+    limits: DecodeLimits,
+    /// Shared with every reader below, and restarted for each frame
+    deadline: Deadline,
+    filter_image: bool,
+    binarizers: Binarizers,
+    reader: DiagnosticReader,
+    filtered_reader: FilteredImageReader<DiagnosticReader>,
+    multi_reader: GenericMultipleBarcodeReader<DiagnosticReader>,
+    filtered_multi_reader: GenericMultipleBarcodeReader<FilteredImageReader<DiagnosticReader>>,
+    luma: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Decoder {
+    /// Create a decoder using a copy of `hints`. Later changes to `hints` are not seen until
+    /// `set_hints` is called. `binarizers` are tried in order, as in the free functions.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        hints: &DecodeHintDictionary,
+        filter_image: Option<bool>,
+        binarizers: Option<Vec<Binarizer>>,
+    ) -> Decoder {
+        let deadline = Deadline::default();
+        let reader = || DiagnosticReader::with_deadline(&deadline);
+        let mut decoder = Decoder {
+            hints: DecodeHints::default(),
+            limits: DecodeLimits::default(),
+            filter_image: matches!(filter_image, Some(true)),
+            binarizers: hints.binarizers(binarizers.unwrap_or_default()),
+            reader: reader(),
+            filtered_reader: FilteredImageReader::new(reader()),
+            multi_reader: GenericMultipleBarcodeReader::new(reader()),
+            filtered_multi_reader: GenericMultipleBarcodeReader::new(FilteredImageReader::new(
+                reader(),
+            )),
+            deadline,
+            luma: Vec::new(),
+        };
+        decoder.set_hints(hints);
+        decoder
+    }
+
+    /// Replace the hints used by later calls. The `max_duration_ms` budget of the hints applies
+    /// to each call on its own, and their abort signal to every call until it is replaced.
+    pub fn set_hints(&mut self, hints: &DecodeHintDictionary) {
+        self.hints = hints.get_dictionary().clone();
+        self.limits = hints.limits().clone();
+        self.binarizers.set_fixed_threshold(hints.fixed_threshold());
+        // match the free decode functions, which all try harder unless told otherwise
+        self.hints.TryHarder = self.hints.TryHarder.or(Some(true));
+        self.reader.set_hints(&self.hints);
+    }
+
+    /// Replace the binarizers tried by later calls. An empty list uses `Binarizer.Hybrid`.
+    pub fn set_binarizers(&mut self, binarizers: Vec<Binarizer>) {
+        self.binarizers.set_list(binarizers);
+    }
+
+    /// Decode a barcode from a frame of raw pixels in any supported `PixelFormat`.
+    ///
+    /// `stride` is the number of bytes from the start of one row to the start of the next.
+    /// Leave it unset, or pass 0, when rows are tightly packed.
+    pub fn decode(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        stride: Option<u32>,
+        pixel_format: PixelFormat,
+    ) -> Result<BarcodeResult, BarcodeError> {
+        let source = self.load_frame(data, width, height, stride, pixel_format)?;
+        self.deadline.restart(&self.limits);
+        let result = if self.filter_image {
+            self.filtered_reader.decode_with_hints(
+                &mut BinaryBitmap::new(HybridBinarizer::new(source.clone())),
+                &self.hints,
+            )
+        } else {
+            let reader = &mut self.reader;
+            binarizer::decode_with_binarizers(source.clone(), &self.binarizers, |image| {
+                reader.decode_with_state(image)
+            })
+        };
+        self.reclaim(source);
+
+        result
+            .map(BarcodeResult::from)
+            .map_err(|error| self.deadline.error(error))
+    }
+
+    /// Decode all barcodes in a frame of raw pixels in any supported `PixelFormat`. A frame that
+    /// runs out of time gives the barcodes found before then, if any.
+    pub fn decode_multi(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        stride: Option<u32>,
+        pixel_format: PixelFormat,
+    ) -> Result<Vec<BarcodeResult>, BarcodeError> {
+        let source = self.load_frame(data, width, height, stride, pixel_format)?;
+        self.deadline.restart(&self.limits);
+        let result = if self.filter_image {
+            self.filtered_multi_reader.decode_multiple_with_hints(
+                &mut BinaryBitmap::new(HybridBinarizer::new(source.clone())),
+                &self.hints,
+            )
+        } else {
+            let (reader, hints) = (&mut self.multi_reader, &self.hints);
+            binarizer::decode_with_binarizers(source.clone(), &self.binarizers, |image| {
+                reader.decode_multiple_with_hints(image, hints)
+            })
+        };
+        self.reclaim(source);
+
+        result
+            .map(|results| results.into_iter().map(BarcodeResult::from).collect())
+            .map_err(|error| self.deadline.error(error))
+    }
+}
+
+impl Decoder {
+    /// Convert a frame into the reusable luma buffer and wrap it in a source the readers can use
+    fn load_frame(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        stride: Option<u32>,
+        pixel_format: PixelFormat,
+    ) -> Result<SharedLumaSource, BarcodeError> {
+        pixel_format::raw_to_luma_into(
+            data,
+            width,
+            height,
+            stride.unwrap_or(0),
+            pixel_format,
+            &mut self.luma,
+        )?;
+        SharedLumaSource::new(std::mem::take(&mut self.luma), width, height)
+    }
+
+    /// Take the luma buffer back once the readers are done with it, so the next frame reuses it
+    fn reclaim(&mut self, source: SharedLumaSource) {
+        if let Some(luma) = source.into_buffer() {
+            self.luma = luma;
+        }
+    }
+}
@@ -129,6 +129,8 @@ pub enum EncodeHintTypes {
     /**
      * Specifies whether the data should be encoded to the GS1 standard (type {@link Boolean}, or "true" or "false"
      * {@link String } value).
+     * Code 128, Data Matrix and QR Code data that starts with "(" is read as application identifiers in the
+     * "(01)09501101530003(10)ABC" form.
      */
     Gs1Format,
 
@@ -425,6 +427,10 @@ impl EncodeHintDictionary {
                 self.0.MaxSize = Some(dim);
             }
             EncodeHintTypes::Margin => {
+                // the writers unwrap the margin, so it is checked here
+                if value.parse::<u32>().is_err() {
+                    return false;
+                }
                 self.0.Margin = Some(value);
             }
             EncodeHintTypes::Pdf417Compact => {
@@ -565,7 +571,17 @@ fn parse_dimensions(dim: &str) -> Option<rxing::pdf417::encoder::Dimensions> {
     }
     let split = dim.find("||")?;
     let (min_cols, max_cols) = parse_dimensions_sub_part(&dim[..split])?;
-    let (min_rows, max_rows) = parse_dimensions_sub_part(&dim[split + 1..])?;
+    let (min_rows, max_rows) = parse_dimensions_sub_part(&dim[split + 2..])?;
+
+    // PDF 417 has 1 to 30 data columns and 3 to 90 rows, the encoder indexes out of bounds
+    // beyond them
+    if !(1..=30).contains(&min_cols)
+        || !(min_cols..=30).contains(&max_cols)
+        || !(3..=90).contains(&min_rows)
+        || !(min_rows..=90).contains(&max_rows)
+    {
+        return None;
+    }
 
     Some(rxing::pdf417::encoder::Dimensions::new(
         min_cols, max_cols, min_rows, max_rows,
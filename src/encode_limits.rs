@@ -0,0 +1,471 @@
+use rxing::common::CharacterSet;
+use rxing::datamatrix::encoder::{
+    high_level_encoder, SymbolInfo, SymbolInfoLookup, SymbolShapeHint,
+};
+use rxing::EncodeHints;
+
+use crate::error::{BarcodeError, ErrorKind};
+use crate::BarcodeFormat;
+
+/// Largest width, height or total margin accepted when encoding. The writers allocate the whole
+/// matrix up front, and running out of memory aborts the wasm instance rather than failing.
+pub(crate) const MAX_ENCODE_DIMENSION: u32 = 16384;
+
+/// rxing draws Telepen symbols into a buffer of this many modules, and indexes past its end rather
+/// than failing when the data doesn't fit
+const TELEPEN_BUFFER_MODULES: usize = 2000;
+
+/// Every Telepen character, including the start, checksum and stop characters, is 16 modules wide
+const TELEPEN_CHARACTER_MODULES: usize = 16;
+
+/// Reject requests rxing's writers would panic on rather than report.
+///
+/// A panic aborts the wasm instance, so everything the writers don't check for themselves is
+/// checked here first.
+pub(crate) fn check_encode(
+    data: &str,
+    width: u32,
+    height: u32,
+    format: BarcodeFormat,
+    hints: &EncodeHints,
+) -> Result<(), BarcodeError> {
+    check_writable(format)?;
+    check_size(width, height, hints)?;
+
+    match format {
+        BarcodeFormat::CODABAR if !data.is_ascii() => Err(BarcodeError::illegal_argument(
+            "Codabar can only encode ASCII characters",
+        )),
+        BarcodeFormat::Telepen => check_telepen(data, hints),
+        BarcodeFormat::DataMatrix => check_data_matrix(data, hints),
+        BarcodeFormat::Pdf417 => check_pdf417(data, hints),
+        BarcodeFormat::AZTEC => check_aztec(data, hints),
+        _ => Ok(()),
+    }
+}
+
+/// Reject the formats rxing has no writer for
+fn check_writable(format: BarcodeFormat) -> Result<(), BarcodeError> {
+    match format {
+        BarcodeFormat::AZTEC
+        | BarcodeFormat::CODABAR
+        | BarcodeFormat::Code39
+        | BarcodeFormat::Code93
+        | BarcodeFormat::Code128
+        | BarcodeFormat::DataMatrix
+        | BarcodeFormat::Ean8
+        | BarcodeFormat::Ean13
+        | BarcodeFormat::ITF
+        | BarcodeFormat::Pdf417
+        | BarcodeFormat::QrCode
+        | BarcodeFormat::UpcA
+        | BarcodeFormat::UpcE
+        | BarcodeFormat::Telepen => Ok(()),
+        _ => Err(BarcodeError::new(
+            ErrorKind::UnsupportedFormat,
+            format!("{format:?} can't be encoded"),
+        )),
+    }
+}
+
+/// Reject image sizes and margins too large to allocate
+pub(crate) fn check_size(width: u32, height: u32, hints: &EncodeHints) -> Result<(), BarcodeError> {
+    if width > MAX_ENCODE_DIMENSION || height > MAX_ENCODE_DIMENSION {
+        return Err(BarcodeError::illegal_argument(format!(
+            "requested size of {width}x{height} is larger than {MAX_ENCODE_DIMENSION}x{MAX_ENCODE_DIMENSION}"
+        )));
+    }
+    // the hint dictionary only accepts margins that parse
+    if let Some(margin) = hints
+        .Margin
+        .as_deref()
+        .and_then(|margin| margin.parse::<u32>().ok())
+        .filter(|margin| *margin > MAX_ENCODE_DIMENSION / 2)
+    {
+        return Err(BarcodeError::illegal_argument(format!(
+            "margin of {margin} is larger than {}",
+            MAX_ENCODE_DIMENSION / 2
+        )));
+    }
+    Ok(())
+}
+
+fn check_telepen(data: &str, hints: &EncodeHints) -> Result<(), BarcodeError> {
+    let characters = if matches!(hints.TelepenAsNumeric, Some(true)) {
+        data.chars().count().div_ceil(2)
+    } else {
+        data.chars().count()
+    };
+    if telepen_modules(characters) > TELEPEN_BUFFER_MODULES {
+        return Err(capacity_exceeded(format!(
+            "Telepen holds at most {} characters, or twice as many digits in numeric mode",
+            telepen_max_characters()
+        )));
+    }
+    Ok(())
+}
+
+/// The width of a Telepen symbol holding `characters` data characters, less the trailing space
+fn telepen_modules(characters: usize) -> usize {
+    (characters + 3) * TELEPEN_CHARACTER_MODULES - 1
+}
+
+fn telepen_max_characters() -> usize {
+    (TELEPEN_BUFFER_MODULES + 1) / TELEPEN_CHARACTER_MODULES - 3
+}
+
+/// The PDF417 writer reports these as writer errors, which are otherwise taken to mean the data
+/// doesn't fit
+fn check_pdf417(data: &str, hints: &EncodeHints) -> Result<(), BarcodeError> {
+    if data.is_empty() {
+        return Err(BarcodeError::illegal_argument(
+            "PDF417 can't encode an empty message",
+        ));
+    }
+    let auto_eci = hints
+        .Pdf417AutoEci
+        .as_deref()
+        .and_then(|auto_eci| auto_eci.parse::<bool>().ok())
+        .unwrap_or(false);
+    if auto_eci {
+        return Ok(());
+    }
+    match hints
+        .CharacterSet
+        .as_deref()
+        .and_then(CharacterSet::get_character_set_by_name)
+    {
+        Some(charset) if charset.encode(data).is_err() => {
+            Err(BarcodeError::illegal_argument(format!(
+                "the data can't be encoded as {}",
+                charset.get_charset_name()
+            )))
+        }
+        None if data.chars().any(|c| c as u32 > 0xFF) => Err(BarcodeError::illegal_argument(
+            "PDF417 can only encode ISO-8859-1 characters without a character set or automatic ECI",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The Aztec writer reports these as illegal arguments, which are otherwise taken to mean the
+/// data doesn't fit
+fn check_aztec(data: &str, hints: &EncodeHints) -> Result<(), BarcodeError> {
+    // negative layers ask for a compact symbol
+    if let Some(layers) = hints
+        .AztecLayers
+        .filter(|layers| !(-4..=32).contains(layers))
+    {
+        return Err(BarcodeError::illegal_argument(format!(
+            "{layers} is not a number of Aztec layers, which run from -4 to 32"
+        )));
+    }
+    let charset = hints
+        .CharacterSet
+        .as_deref()
+        .and_then(CharacterSet::get_character_set_by_name)
+        .unwrap_or(CharacterSet::ISO8859_1);
+    if charset.encode(data).is_err() {
+        return Err(BarcodeError::illegal_argument(format!(
+            "the data can't be encoded as {}",
+            charset.get_charset_name()
+        )));
+    }
+    Ok(())
+}
+
+/// rxing's Data Matrix encoder panics when the data outgrows the largest symbol allowed by the
+/// shape and size hints, so the data is first encoded against the capacities of the allowed
+/// symbols with one oversized symbol added at the end, which catches the overflow instead.
+fn check_data_matrix(data: &str, hints: &EncodeHints) -> Result<(), BarcodeError> {
+    let capacities = data_matrix_capacities(hints);
+    let Some(&largest) = capacities.last() else {
+        return Err(BarcodeError::illegal_argument(
+            "no Data Matrix symbol matches the shape and size hints",
+        ));
+    };
+    let largest = largest as usize;
+
+    // no character takes fewer than half a codeword
+    let characters = data.chars().count();
+    if characters > largest * 2 {
+        return Err(data_matrix_full(largest));
+    }
+
+    let force_c40 = matches!(hints.ForceC40, Some(true));
+    if matches!(hints.DataMatrixCompact, Some(true)) {
+        // the minimal encoder overflows while weighing up encodings that don't fit. Any data fits
+        // as bytes, and the minimal encoding is never longer than the default one, so either
+        // bound is enough
+        if data.len() + DATA_MATRIX_BYTE_OVERHEAD <= largest
+            || data_matrix_codewords(data, &capacities, characters, false)
+                .is_some_and(|codewords| codewords <= largest)
+        {
+            return Ok(());
+        }
+        return Err(data_matrix_full(largest));
+    }
+
+    match data_matrix_codewords(data, &capacities, characters, force_c40) {
+        Some(codewords) if codewords > largest => Err(data_matrix_full(largest)),
+        // anything the encoder rejects is reported by the writer
+        _ => Ok(()),
+    }
+}
+
+/// The distinct data capacities, smallest first, of rxing's Data Matrix symbols that the shape and
+/// size hints allow
+#[allow(deprecated)]
+fn data_matrix_capacities(hints: &EncodeHints) -> Vec<u32> {
+    let shape = hints.DataMatrixShape.unwrap_or(SymbolShapeHint::FORCE_NONE);
+    let lookup = SymbolInfoLookup::new();
+    let mut capacities = Vec::new();
+    // each lookup finds the smallest symbol holding more than the last one found
+    while let Ok(Some(symbol)) = lookup.lookup_with_codewords_shape_size_fail(
+        capacities.last().map_or(1, |capacity| capacity + 1),
+        shape,
+        &hints.MinSize,
+        &hints.MaxSize,
+        false,
+    ) {
+        capacities.push(symbol.getDataCapacity());
+    }
+    capacities
+}
+
+/// The number of data codewords, with padding, the default encoder uses for `data` with symbols
+/// of the given `capacities`, or `None` if it can't encode it
+fn data_matrix_codewords(
+    data: &str,
+    capacities: &[u32],
+    characters: usize,
+    force_c40: bool,
+) -> Option<usize> {
+    // the high level encoder only looks at how much data a symbol holds, so stand-ins with the same
+    // capacities encode the data the same way
+    let mut symbols: Vec<SymbolInfo> = capacities
+        .iter()
+        .map(|&capacity| SymbolInfo::new(false, capacity, 0, 1, 1, 1))
+        .collect();
+    // no character takes more than four codewords, including mode switches
+    symbols.push(SymbolInfo::new(
+        false,
+        (characters * 4 + 16) as u32,
+        0,
+        1,
+        1,
+        1,
+    ));
+    let mut lookup = SymbolInfoLookup::new();
+    lookup.overrideSymbolSet(&symbols);
+    let encoded = high_level_encoder::encodeHighLevelWithDimensionForceC40WithSymbolInfoLookup(
+        data,
+        SymbolShapeHint::FORCE_NONE,
+        None,
+        None,
+        force_c40,
+        Some(lookup),
+    )
+    .ok()?;
+    Some(encoded.chars().count())
+}
+
+/// Codewords besides the data itself needed to encode bytes: an ECI, the switch to byte mode and
+/// a two codeword length
+const DATA_MATRIX_BYTE_OVERHEAD: usize = 8;
+
+fn data_matrix_full(largest: usize) -> BarcodeError {
+    capacity_exceeded(format!(
+        "the data needs more than the {largest} codewords a Data Matrix symbol of the requested shape and size holds"
+    ))
+}
+
+fn capacity_exceeded(message: impl Into<String>) -> BarcodeError {
+    BarcodeError::new(ErrorKind::EncodeCapacityExceeded, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rxing::oned::{OneDimensionalCodeWriter, TelepenWriter};
+    use rxing::Dimension;
+
+    fn check(data: &str, format: BarcodeFormat, hints: &EncodeHints) -> Option<ErrorKind> {
+        check_encode(data, 100, 100, format, hints)
+            .err()
+            .map(|error| error.kind())
+    }
+
+    fn write(data: &str, format: BarcodeFormat, hints: &EncodeHints) -> Option<ErrorKind> {
+        crate::write_bit_matrix(data, 100, 100, format, hints)
+            .err()
+            .map(|error| error.kind())
+    }
+
+    #[test]
+    fn writer_errors_are_classified() {
+        let hints = EncodeHints::default();
+        let too_much = "x".repeat(3000);
+        for format in [
+            BarcodeFormat::QrCode,
+            BarcodeFormat::Pdf417,
+            BarcodeFormat::AZTEC,
+        ] {
+            assert_eq!(write("12345", format, &hints), None, "{format:?}");
+            assert_eq!(
+                write(&too_much, format, &hints),
+                Some(ErrorKind::EncodeCapacityExceeded),
+                "{format:?}"
+            );
+        }
+        assert_eq!(
+            write("12345", BarcodeFormat::MAXICODE, &hints),
+            Some(ErrorKind::UnsupportedFormat)
+        );
+        assert_eq!(
+            write("letters", BarcodeFormat::Ean13, &hints),
+            Some(ErrorKind::IllegalArgument)
+        );
+    }
+
+    #[test]
+    fn pdf417_input_errors_are_not_capacity() {
+        let hints = EncodeHints::default();
+        assert_eq!(
+            write("", BarcodeFormat::Pdf417, &hints),
+            Some(ErrorKind::IllegalArgument)
+        );
+        assert_eq!(
+            write("\u{20ac}", BarcodeFormat::Pdf417, &hints),
+            Some(ErrorKind::IllegalArgument)
+        );
+        let utf8 = EncodeHints {
+            CharacterSet: Some("UTF-8".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(write("\u{20ac}", BarcodeFormat::Pdf417, &utf8), None);
+        let latin1 = EncodeHints {
+            CharacterSet: Some("ISO-8859-1".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            write("\u{20ac}", BarcodeFormat::Pdf417, &latin1),
+            Some(ErrorKind::IllegalArgument)
+        );
+    }
+
+    #[test]
+    fn aztec_input_errors_are_not_capacity() {
+        let hints = EncodeHints::default();
+        assert_eq!(
+            write("\u{20ac}", BarcodeFormat::AZTEC, &hints),
+            Some(ErrorKind::IllegalArgument)
+        );
+        let layers = |layers| EncodeHints {
+            AztecLayers: Some(layers),
+            ..Default::default()
+        };
+        assert_eq!(
+            write("12345", BarcodeFormat::AZTEC, &layers(33)),
+            Some(ErrorKind::IllegalArgument)
+        );
+        assert_eq!(
+            write("12345", BarcodeFormat::AZTEC, &layers(-5)),
+            Some(ErrorKind::IllegalArgument)
+        );
+        assert_eq!(
+            write(&"12345".repeat(20), BarcodeFormat::AZTEC, &layers(-1)),
+            Some(ErrorKind::EncodeCapacityExceeded)
+        );
+    }
+
+    #[test]
+    fn telepen_limit_matches_rxings_buffer() {
+        let longest = "A".repeat(telepen_max_characters());
+        let hints = EncodeHints::default();
+        assert_eq!(check(&longest, BarcodeFormat::Telepen, &hints), None);
+        assert!(TelepenWriter
+            .encode_oned_with_hints(&longest, &hints)
+            .is_ok());
+
+        let too_long = format!("{longest}A");
+        assert_eq!(
+            check(&too_long, BarcodeFormat::Telepen, &hints),
+            Some(ErrorKind::EncodeCapacityExceeded)
+        );
+        // once rxing reports this itself the check can go
+        assert!(
+            std::panic::catch_unwind(|| TelepenWriter.encode_oned_with_hints(&too_long, &hints))
+                .is_err(),
+            "rxing no longer panics on long Telepen data"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn data_matrix_capacities_come_from_rxing() {
+        let capacities = data_matrix_capacities(&EncodeHints::default());
+        assert_eq!(capacities.first(), Some(&3));
+        assert_eq!(capacities.last(), Some(&1558));
+        assert!(capacities.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let rectangles = EncodeHints {
+            DataMatrixShape: Some(SymbolShapeHint::FORCE_RECTANGLE),
+            ..Default::default()
+        };
+        assert_eq!(data_matrix_capacities(&rectangles).last(), Some(&49));
+
+        let small = EncodeHints {
+            MaxSize: Some(Dimension::new(20, 20)),
+            ..Default::default()
+        };
+        assert_eq!(data_matrix_capacities(&small).last(), Some(&22));
+
+        let none = EncodeHints {
+            DataMatrixShape: Some(SymbolShapeHint::FORCE_RECTANGLE),
+            MaxSize: Some(Dimension::new(12, 12)),
+            ..Default::default()
+        };
+        assert!(data_matrix_capacities(&none).is_empty());
+        assert_eq!(
+            check("A", BarcodeFormat::DataMatrix, &none),
+            Some(ErrorKind::IllegalArgument)
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn data_matrix_overflow_is_reported() {
+        let hints = EncodeHints::default();
+        assert_eq!(
+            check(&"a".repeat(1500), BarcodeFormat::DataMatrix, &hints),
+            None
+        );
+        assert_eq!(
+            check(&"a".repeat(2400), BarcodeFormat::DataMatrix, &hints),
+            Some(ErrorKind::EncodeCapacityExceeded)
+        );
+
+        let small = EncodeHints {
+            MaxSize: Some(Dimension::new(20, 20)),
+            ..Default::default()
+        };
+        assert_eq!(
+            check(&"a".repeat(20), BarcodeFormat::DataMatrix, &small),
+            None
+        );
+        assert_eq!(
+            check(&"a".repeat(60), BarcodeFormat::DataMatrix, &small),
+            Some(ErrorKind::EncodeCapacityExceeded)
+        );
+
+        let compact = EncodeHints {
+            DataMatrixCompact: Some(true),
+            ..small
+        };
+        assert_eq!(
+            check(&"a".repeat(60), BarcodeFormat::DataMatrix, &compact),
+            Some(ErrorKind::EncodeCapacityExceeded)
+        );
+    }
+}
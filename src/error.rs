@@ -0,0 +1,209 @@
+use std::fmt;
+
+use rxing::Exceptions;
+use wasm_bindgen::prelude::*;
+
+use crate::BarcodeFormat;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_BARCODE_ERROR: &'static str = r#"
+/** The kinds of failure reported by `BarcodeError.kind` */
+export type BarcodeErrorKind =
+    | "NotFound"
+    | "Checksum"
+    | "Format"
+    | "InvalidInput"
+    | "UnsupportedFormat"
+    | "EncodeCapacityExceeded"
+    | "IllegalArgument"
+    | "Timeout"
+    | "Aborted";
+
+/** The error thrown by every function in this package */
+export interface BarcodeError extends Error {
+    name: "BarcodeError";
+    kind: BarcodeErrorKind;
+}
+"#;
+
+/// The class of the errors thrown to javascript, so they can be told apart with
+/// `err instanceof BarcodeError`.
+///
+/// It is never constructed from javascript. The thrown errors are plain `Error` objects moved onto
+/// its prototype, which is itself moved onto `Error.prototype` so they stay javascript errors.
+#[wasm_bindgen(js_name = BarcodeError)]
+pub struct BarcodeErrorClass {
+    _private: (),
+}
+
+thread_local! {
+    static BARCODE_ERROR_PROTOTYPE: js_sys::Object = {
+        let class: JsValue = BarcodeErrorClass { _private: () }.into();
+        let prototype = js_sys::Object::get_prototype_of(&class);
+        let error_prototype = js_sys::Object::get_prototype_of(&js_sys::Error::new(""));
+        js_sys::Object::set_prototype_of(&prototype, &error_prototype);
+        // setting a property on the prototype of an exported class cannot fail
+        let _ = js_sys::Reflect::set(
+            &prototype,
+            &JsValue::from_str("name"),
+            &JsValue::from_str("BarcodeError"),
+        );
+        prototype
+    };
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+/// Why a call failed, exposed to javascript as the `kind` property of the thrown error
+pub enum ErrorKind {
+    /// No barcode was found
+    NotFound,
+
+    /// A barcode was found, but its error correction or check digits did not match
+    Checksum,
+
+    /// A barcode was found, but its contents could not be decoded
+    Format,
+
+    /// The pixel buffer does not match the dimensions, stride or layout it was given with
+    InvalidInput,
+
+    /// The barcode or image format is not supported
+    UnsupportedFormat,
+
+    /// The data does not fit in the requested barcode format, version or size
+    EncodeCapacityExceeded,
+
+    /// An argument, such as the data to encode, a hint or a color, is not valid
+    IllegalArgument,
+
+    /// The decode ran out of its `max_duration_ms` budget before finding a barcode
+    Timeout,
+
+    /// The decode was cancelled through its abort signal
+    Aborted,
+}
+
+impl ErrorKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "NotFound",
+            ErrorKind::Checksum => "Checksum",
+            ErrorKind::Format => "Format",
+            ErrorKind::InvalidInput => "InvalidInput",
+            ErrorKind::UnsupportedFormat => "UnsupportedFormat",
+            ErrorKind::EncodeCapacityExceeded => "EncodeCapacityExceeded",
+            ErrorKind::IllegalArgument => "IllegalArgument",
+            ErrorKind::Timeout => "Timeout",
+            ErrorKind::Aborted => "Aborted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The error returned by every fallible function.
+///
+/// It is thrown to javascript as an `Error` named "BarcodeError" that is also an instance of the
+/// exported `BarcodeError` class, with a `kind` property holding the name of its `ErrorKind`.
+pub struct BarcodeError {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl BarcodeError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub(crate) fn illegal_argument(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::IllegalArgument, message)
+    }
+
+    #[cfg(feature = "decode_hints")]
+    /// Whether a decode failed because nothing could be read from the image, rather than because
+    /// of the input or setup, so a stream of frames can carry on to the next one
+    pub(crate) fn is_unreadable(&self) -> bool {
+        matches!(
+            self.kind,
+            ErrorKind::NotFound | ErrorKind::Checksum | ErrorKind::Format | ErrorKind::Timeout
+        )
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Classify an error from rxing's writers.
+    ///
+    /// The QR code and PDF417 writers report data that doesn't fit as writer errors, and the Aztec
+    /// writer as illegal arguments. `encode_limits::check_encode` rejects the other causes of
+    /// those errors before the writer is called, so they are all down to capacity.
+    pub(crate) fn from_encode(error: Exceptions, format: BarcodeFormat) -> Self {
+        match (&error, format) {
+            (Exceptions::WriterException(_), _)
+            | (Exceptions::IllegalArgumentException(_), BarcodeFormat::AZTEC) => {
+                Self::new(ErrorKind::EncodeCapacityExceeded, error.to_string())
+            }
+            _ => error.into(),
+        }
+    }
+}
+
+impl From<Exceptions> for BarcodeError {
+    fn from(error: Exceptions) -> Self {
+        let kind = match &error {
+            Exceptions::NotFoundException(_) => ErrorKind::NotFound,
+            Exceptions::ChecksumException(_) | Exceptions::ReedSolomonException(_) => {
+                ErrorKind::Checksum
+            }
+            Exceptions::IllegalArgumentException(_) | Exceptions::WriterException(_) => {
+                ErrorKind::IllegalArgument
+            }
+            Exceptions::UnsupportedOperationException(_) => ErrorKind::UnsupportedFormat,
+            // everything else went wrong while reading a symbol that was found
+            Exceptions::FormatException(_)
+            | Exceptions::ParseException(_)
+            | Exceptions::ReaderException(_)
+            | Exceptions::ReaderDecodeException()
+            | Exceptions::IllegalStateException(_)
+            | Exceptions::ArithmeticException(_)
+            | Exceptions::IndexOutOfBoundsException(_)
+            | Exceptions::RuntimeException(_) => ErrorKind::Format,
+        };
+        Self::new(kind, error.to_string())
+    }
+}
+
+impl fmt::Display for BarcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.message)
+    }
+}
+
+impl std::error::Error for BarcodeError {}
+
+impl From<BarcodeError> for JsValue {
+    fn from(value: BarcodeError) -> Self {
+        let error = js_sys::Error::new(&value.message);
+        error.set_name("BarcodeError");
+        // setting a property on a fresh Error object cannot fail
+        let _ = js_sys::Reflect::set(
+            &error,
+            &JsValue::from_str("kind"),
+            &JsValue::from_str(value.kind.as_str()),
+        );
+        BARCODE_ERROR_PROTOTYPE
+            .with(|prototype| js_sys::Object::set_prototype_of(&error, prototype));
+        error.into()
+    }
+}
@@ -0,0 +1,265 @@
+use crate::region::Region;
+use crate::BarcodeFormat;
+
+/// An (x, y) position in image coordinates
+type Point = (f32, f32);
+
+/// Linear barcodes are only located along the row that was scanned, so their quad reaches this
+/// far either side of it
+const LINE_HALF_WIDTH: f32 = 1.0;
+
+/// The corners of a symbol in image coordinates, clockwise from the symbol's own top-left:
+/// top-left, top-right, bottom-right, bottom-left.
+///
+/// `points` are the flattened result points, whose layout depends on the reader that found the
+/// symbol. Readers agree on where to start but not on which way round to go, so the corners of
+/// 2D symbols are put in clockwise order rather than trusted. Returns `None` if there are no
+/// points to work from.
+pub(crate) fn corners(format: BarcodeFormat, points: &[f32]) -> Option<[Point; 4]> {
+    let points: Vec<Point> = points
+        .chunks_exact(2)
+        .map(|point| (point[0], point[1]))
+        .collect();
+    match (format, points.as_slice()) {
+        // the corners from the top-left, clockwise from the newer detectors and anticlockwise
+        // from rxing's older Data Matrix detector
+        (
+            BarcodeFormat::QrCode
+            | BarcodeFormat::MicroQR
+            | BarcodeFormat::RectangularMicroQR
+            | BarcodeFormat::DataMatrix,
+            [top_left, top_right, bottom_right, bottom_left, ..],
+        ) => Some(clockwise([
+            *top_left,
+            *top_right,
+            *bottom_right,
+            *bottom_left,
+        ])),
+        // the corners, starting from the top-right
+        (BarcodeFormat::AZTEC, [top_right, bottom_right, bottom_left, top_left, ..]) => {
+            Some(clockwise([
+                *top_left,
+                *top_right,
+                *bottom_right,
+                *bottom_left,
+            ]))
+        }
+        // the outer corners down the left then the right, followed by the inner corners. Corners
+        // that weren't found are left out, which shifts the rest
+        (BarcodeFormat::Pdf417, [top_left, bottom_left, top_right, bottom_right, _, _, _, _]) => {
+            Some(clockwise([
+                *top_left,
+                *top_right,
+                *bottom_right,
+                *bottom_left,
+            ]))
+        }
+        (format, [start, .., end]) if is_linear(format) => Some(line_quad(*start, *end)),
+        (_, []) => None,
+        (_, points) => Some(enclosing_quad(points)),
+    }
+}
+
+/// The smallest whole pixel rectangle containing every corner, clipped at the top and left
+/// edges of the image
+pub(crate) fn bounding_box(corners: &[Point; 4]) -> Region {
+    let (left, top, right, bottom) = extent(corners);
+    // float to int casts saturate, so negative positions become 0
+    let (left, top) = (left.floor() as u32, top.floor() as u32);
+    let (right, bottom) = (right.ceil() as u32, bottom.ceil() as u32);
+    Region::new(left, top, right - left, bottom - top)
+}
+
+/// The average of the corners
+pub(crate) fn center(corners: &[Point; 4]) -> Point {
+    let (x, y) = corners
+        .iter()
+        .fold((0.0, 0.0), |(sum_x, sum_y), (x, y)| (sum_x + x, sum_y + y));
+    (x / 4.0, y / 4.0)
+}
+
+/// The angle of the symbol's top edge in degrees, clockwise from the x axis since y grows
+/// downwards, in the range -180 to 180
+pub(crate) fn rotation(corners: &[Point; 4]) -> f32 {
+    let [(left_x, left_y), (right_x, right_y), ..] = *corners;
+    (right_y - left_y).atan2(right_x - left_x).to_degrees()
+}
+
+fn is_linear(format: BarcodeFormat) -> bool {
+    matches!(
+        format,
+        BarcodeFormat::CODABAR
+            | BarcodeFormat::Code39
+            | BarcodeFormat::Code93
+            | BarcodeFormat::Code128
+            | BarcodeFormat::Ean8
+            | BarcodeFormat::Ean13
+            | BarcodeFormat::ITF
+            | BarcodeFormat::Rss14
+            | BarcodeFormat::RssExpanded
+            | BarcodeFormat::UpcA
+            | BarcodeFormat::UpcE
+            | BarcodeFormat::UpcEanExtension
+            | BarcodeFormat::Telepen
+    )
+}
+
+/// `quad` with its corners running clockwise on screen, keeping the first corner first
+fn clockwise(quad: [Point; 4]) -> [Point; 4] {
+    let [first, second, third, fourth] = quad;
+    if signed_area(&quad) < 0.0 {
+        [first, fourth, third, second]
+    } else {
+        quad
+    }
+}
+
+/// A thin quad around the scan line from `start` to `end`
+fn line_quad(start: Point, end: Point) -> [Point; 4] {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx.hypot(dy);
+    // a perpendicular pointing below the line, straight down if the line has no direction
+    let (normal_x, normal_y) = if length > 0.0 {
+        (
+            -dy / length * LINE_HALF_WIDTH,
+            dx / length * LINE_HALF_WIDTH,
+        )
+    } else {
+        (0.0, LINE_HALF_WIDTH)
+    };
+    [
+        (start.0 - normal_x, start.1 - normal_y),
+        (end.0 - normal_x, end.1 - normal_y),
+        (end.0 + normal_x, end.1 + normal_y),
+        (start.0 + normal_x, start.1 + normal_y),
+    ]
+}
+
+/// An upright rectangle around points whose layout isn't known
+fn enclosing_quad(points: &[Point]) -> [Point; 4] {
+    let (mut left, mut top, mut right, mut bottom) = extent(points);
+    if bottom - top < LINE_HALF_WIDTH * 2.0 {
+        top -= LINE_HALF_WIDTH;
+        bottom += LINE_HALF_WIDTH;
+    }
+    if right - left < LINE_HALF_WIDTH * 2.0 {
+        left -= LINE_HALF_WIDTH;
+        right += LINE_HALF_WIDTH;
+    }
+    [(left, top), (right, top), (right, bottom), (left, bottom)]
+}
+
+/// The left, top, right and bottom edges of the smallest upright rectangle around `points`
+pub(crate) fn extent(points: &[Point]) -> (f32, f32, f32, f32) {
+    points.iter().fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(left, top, right, bottom), (x, y)| {
+            (left.min(*x), top.min(*y), right.max(*x), bottom.max(*y))
+        },
+    )
+}
+
+#[cfg(feature = "decode_hints")]
+/// How much two convex quads overlap, as the area they share over the area they cover between
+/// them: 1 for the same quad and 0 for quads that don't touch
+pub(crate) fn overlap(a: &[Point; 4], b: &[Point; 4]) -> f32 {
+    let shared = signed_area(&clip(a, b)).abs();
+    let covered = signed_area(a).abs() + signed_area(b).abs() - shared;
+    if covered > 0.0 {
+        shared / covered
+    } else {
+        0.0
+    }
+}
+
+/// The area inside a polygon, positive when its corners run clockwise on screen
+fn signed_area(polygon: &[Point]) -> f32 {
+    let twice_area: f32 = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+        .sum();
+    twice_area / 2.0
+}
+
+#[cfg(feature = "decode_hints")]
+/// The part of the convex polygon `subject` inside the convex quad `window`, by clipping it
+/// against each edge of the window in turn
+fn clip(subject: &[Point], window: &[Point; 4]) -> Vec<Point> {
+    // which side of an edge is inside depends on which way round the window's corners run
+    let direction = signed_area(window).signum();
+    let inside = |(x1, y1): Point, (x2, y2): Point, (x, y): Point| {
+        ((x2 - x1) * (y - y1) - (y2 - y1) * (x - x1)) * direction >= 0.0
+    };
+    let crossing = |(x1, y1): Point, (x2, y2): Point, (sx, sy): Point, (ex, ey): Point| {
+        let (edge_x, edge_y) = (x2 - x1, y2 - y1);
+        let from_start = edge_x * (sy - y1) - edge_y * (sx - x1);
+        let from_end = edge_x * (ey - y1) - edge_y * (ex - x1);
+        let t = from_start / (from_start - from_end);
+        (sx + (ex - sx) * t, sy + (ey - sy) * t)
+    };
+
+    let mut polygon = subject.to_vec();
+    for (&edge_start, &edge_end) in window.iter().zip(window.iter().cycle().skip(1)) {
+        let input = std::mem::take(&mut polygon);
+        for (&start, &end) in input.iter().zip(input.iter().cycle().skip(1)) {
+            match (
+                inside(edge_start, edge_end, start),
+                inside(edge_start, edge_end, end),
+            ) {
+                (true, true) => polygon.push(end),
+                (true, false) => polygon.push(crossing(edge_start, edge_end, start, end)),
+                (false, true) => {
+                    polygon.push(crossing(edge_start, edge_end, start, end));
+                    polygon.push(end);
+                }
+                (false, false) => {}
+            }
+        }
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flatten(points: &[Point]) -> Vec<f32> {
+        points.iter().flat_map(|(x, y)| [*x, *y]).collect()
+    }
+
+    const TOP_LEFT: Point = (10.0, 20.0);
+    const TOP_RIGHT: Point = (50.0, 24.0);
+    const BOTTOM_RIGHT: Point = (46.0, 64.0);
+    const BOTTOM_LEFT: Point = (6.0, 60.0);
+
+    #[test]
+    fn data_matrix_corners_from_either_detector_agree() {
+        let expected = [TOP_LEFT, TOP_RIGHT, BOTTOM_RIGHT, BOTTOM_LEFT];
+        let newer = flatten(&expected);
+        // rxing's older detector, the fallback when trying harder
+        let legacy = flatten(&[TOP_LEFT, BOTTOM_LEFT, BOTTOM_RIGHT, TOP_RIGHT]);
+        for points in [newer, legacy] {
+            let corners = corners(BarcodeFormat::DataMatrix, &points).unwrap();
+            assert_eq!(corners, expected);
+            assert!((rotation(&corners) - 5.71).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn aztec_corners_start_from_the_top_left() {
+        let points = flatten(&[TOP_RIGHT, BOTTOM_RIGHT, BOTTOM_LEFT, TOP_LEFT]);
+        assert_eq!(
+            corners(BarcodeFormat::AZTEC, &points),
+            Some([TOP_LEFT, TOP_RIGHT, BOTTOM_RIGHT, BOTTOM_LEFT])
+        );
+    }
+
+    #[test]
+    fn an_upside_down_symbol_keeps_its_own_top_left() {
+        let quad = [BOTTOM_RIGHT, BOTTOM_LEFT, TOP_LEFT, TOP_RIGHT];
+        let corners = corners(BarcodeFormat::QrCode, &flatten(&quad)).unwrap();
+        assert_eq!(corners, quad);
+        assert!((rotation(&corners).abs() - 174.29).abs() < 0.01);
+    }
+}
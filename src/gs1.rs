@@ -0,0 +1,859 @@
+use wasm_bindgen::prelude::*;
+
+use crate::error::{BarcodeError, ErrorKind};
+use crate::result_metadata::set;
+use crate::BarcodeResult;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_GS1_ELEMENT: &'static str = r#"
+/** One application identifier of a GS1 element string and its value */
+export interface Gs1Element {
+    ai: string;
+    title: string;
+    /** The value as it appears in the data */
+    value: string;
+    /** Set for date AIs such as (17), at midnight UTC. A day of 00 becomes the last day of the month */
+    date?: Date;
+    /** Set for AIs with decimal places such as (3103), and for amounts */
+    number?: number;
+    /** The ISO 4217 currency code of amounts such as (3912) */
+    currency?: string;
+}
+"#;
+
+/// The group separator that ends a variable length field, which readers return for FNC1
+pub(crate) const GROUP_SEPARATOR: char = '\u{1D}';
+
+/// The first two digits of the AIs whose length is predefined, which don't need a separator
+const PREDEFINED_LENGTH: [&str; 22] = [
+    "00", "01", "02", "03", "04", "11", "12", "13", "14", "15", "16", "17", "18", "19", "20", "31",
+    "32", "33", "34", "35", "36", "41",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Content {
+    Numeric,
+    /// GS1 AI encodable character set 82
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Plain,
+    /// A YYMMDD date
+    Date,
+    /// A number with as many decimal places as the last digit of the AI
+    Decimal,
+    /// An ISO 4217 currency code followed by a `Decimal` amount
+    Amount,
+}
+
+#[derive(Debug)]
+/// The format of the value of one application identifier, from the GS1 General Specifications
+pub(crate) struct AiDefinition {
+    /// The AI, or its first three digits if `indexed`
+    ai: &'static str,
+    title: &'static str,
+    /// The last digit of the AI can be any digit, giving the decimal places for decimal AIs
+    indexed: bool,
+    /// Digits the value starts with, such as a GTIN or a currency code
+    digits: usize,
+    min: usize,
+    max: usize,
+    /// What follows the leading `digits`
+    content: Content,
+    /// The last of the leading `digits` is a GS1 check digit
+    check_digit: bool,
+    kind: Kind,
+}
+
+impl AiDefinition {
+    const fn check_digit(self) -> Self {
+        Self {
+            check_digit: true,
+            ..self
+        }
+    }
+
+    /// Check the digit that ends the first `digits` digits, for values that go on after it
+    const fn check_digit_at(self, digits: usize) -> Self {
+        Self {
+            digits,
+            check_digit: true,
+            ..self
+        }
+    }
+
+    const fn date(self) -> Self {
+        Self {
+            kind: Kind::Date,
+            ..self
+        }
+    }
+
+    const fn decimal(self) -> Self {
+        Self {
+            indexed: true,
+            kind: Kind::Decimal,
+            ..self
+        }
+    }
+
+    const fn amount(self) -> Self {
+        Self {
+            indexed: true,
+            kind: Kind::Amount,
+            ..self
+        }
+    }
+
+    const fn indexed(self) -> Self {
+        Self {
+            indexed: true,
+            ..self
+        }
+    }
+
+    /// Start the value with `digits` digits, which may be followed by nothing
+    const fn after_digits(self, digits: usize) -> Self {
+        Self {
+            digits,
+            min: digits,
+            max: self.max + digits,
+            ..self
+        }
+    }
+
+    fn is_fixed_length(&self) -> bool {
+        self.min == self.max
+    }
+}
+
+/// A value of exactly `length` digits
+const fn numeric(ai: &'static str, title: &'static str, length: usize) -> AiDefinition {
+    AiDefinition {
+        ai,
+        title,
+        indexed: false,
+        digits: length,
+        min: length,
+        max: length,
+        content: Content::Numeric,
+        check_digit: false,
+        kind: Kind::Plain,
+    }
+}
+
+/// A value of up to `max` digits
+const fn numeric_up_to(ai: &'static str, title: &'static str, max: usize) -> AiDefinition {
+    AiDefinition {
+        min: 1,
+        max,
+        digits: 0,
+        ..numeric(ai, title, max)
+    }
+}
+
+/// A value of up to `max` characters
+const fn text(ai: &'static str, title: &'static str, max: usize) -> AiDefinition {
+    AiDefinition {
+        content: Content::Text,
+        ..numeric_up_to(ai, title, max)
+    }
+}
+
+/// A value of exactly `length` characters
+const fn text_of(ai: &'static str, title: &'static str, length: usize) -> AiDefinition {
+    AiDefinition {
+        min: length,
+        ..text(ai, title, length)
+    }
+}
+
+#[rustfmt::skip]
+static DEFINITIONS: &[AiDefinition] = &[
+    numeric("00", "SSCC", 18).check_digit(),
+    numeric("01", "GTIN", 14).check_digit(),
+    numeric("02", "CONTENT", 14).check_digit(),
+    numeric("03", "MTO GTIN", 14).check_digit(),
+    text("10", "BATCH/LOT", 20),
+    numeric("11", "PROD DATE", 6).date(),
+    numeric("12", "DUE DATE", 6).date(),
+    numeric("13", "PACK DATE", 6).date(),
+    numeric("15", "BEST BEFORE or BEST BY", 6).date(),
+    numeric("16", "SELL BY", 6).date(),
+    numeric("17", "USE BY OR EXPIRY", 6).date(),
+    numeric("20", "VARIANT", 2),
+    text("21", "SERIAL", 20),
+    text("22", "CPV", 20),
+    text("235", "TPX", 28),
+    text("240", "ADDITIONAL ID", 30),
+    text("241", "CUST. PART No.", 30),
+    numeric_up_to("242", "MTO VARIANT", 6),
+    text("243", "PCN", 20),
+    text("250", "SECONDARY SERIAL", 30),
+    text("251", "REF. TO SOURCE", 30),
+    text("253", "GDTI", 17).after_digits(13).check_digit(),
+    text("254", "GLN EXTENSION COMPONENT", 20),
+    numeric_up_to("255", "GCN", 12).after_digits(13).check_digit(),
+    numeric_up_to("30", "VAR. COUNT", 8),
+    numeric("310", "NET WEIGHT (kg)", 6).decimal(),
+    numeric("311", "LENGTH (m)", 6).decimal(),
+    numeric("312", "WIDTH (m)", 6).decimal(),
+    numeric("313", "HEIGHT (m)", 6).decimal(),
+    numeric("314", "AREA (m2)", 6).decimal(),
+    numeric("315", "NET VOLUME (l)", 6).decimal(),
+    numeric("316", "NET VOLUME (m3)", 6).decimal(),
+    numeric("320", "NET WEIGHT (lb)", 6).decimal(),
+    numeric("321", "LENGTH (in)", 6).decimal(),
+    numeric("322", "LENGTH (ft)", 6).decimal(),
+    numeric("323", "LENGTH (yd)", 6).decimal(),
+    numeric("324", "WIDTH (in)", 6).decimal(),
+    numeric("325", "WIDTH (ft)", 6).decimal(),
+    numeric("326", "WIDTH (yd)", 6).decimal(),
+    numeric("327", "HEIGHT (in)", 6).decimal(),
+    numeric("328", "HEIGHT (ft)", 6).decimal(),
+    numeric("329", "HEIGHT (yd)", 6).decimal(),
+    numeric("330", "GROSS WEIGHT (kg)", 6).decimal(),
+    numeric("331", "LENGTH (m), log", 6).decimal(),
+    numeric("332", "WIDTH (m), log", 6).decimal(),
+    numeric("333", "HEIGHT (m), log", 6).decimal(),
+    numeric("334", "AREA (m2), log", 6).decimal(),
+    numeric("335", "VOLUME (l), log", 6).decimal(),
+    numeric("336", "VOLUME (m3), log", 6).decimal(),
+    numeric("337", "KG PER m2", 6).decimal(),
+    numeric("340", "GROSS WEIGHT (lb)", 6).decimal(),
+    numeric("341", "LENGTH (in), log", 6).decimal(),
+    numeric("342", "LENGTH (ft), log", 6).decimal(),
+    numeric("343", "LENGTH (yd), log", 6).decimal(),
+    numeric("344", "WIDTH (in), log", 6).decimal(),
+    numeric("345", "WIDTH (ft), log", 6).decimal(),
+    numeric("346", "WIDTH (yd), log", 6).decimal(),
+    numeric("347", "HEIGHT (in), log", 6).decimal(),
+    numeric("348", "HEIGHT (ft), log", 6).decimal(),
+    numeric("349", "HEIGHT (yd), log", 6).decimal(),
+    numeric("350", "AREA (in2)", 6).decimal(),
+    numeric("351", "AREA (ft2)", 6).decimal(),
+    numeric("352", "AREA (yd2)", 6).decimal(),
+    numeric("353", "AREA (in2), log", 6).decimal(),
+    numeric("354", "AREA (ft2), log", 6).decimal(),
+    numeric("355", "AREA (yd2), log", 6).decimal(),
+    numeric("356", "NET WEIGHT (troy oz)", 6).decimal(),
+    numeric("357", "NET VOLUME (oz)", 6).decimal(),
+    numeric("360", "NET VOLUME (qt)", 6).decimal(),
+    numeric("361", "NET VOLUME (gal.)", 6).decimal(),
+    numeric("362", "VOLUME (qt), log", 6).decimal(),
+    numeric("363", "VOLUME (gal.), log", 6).decimal(),
+    numeric("364", "VOLUME (in3)", 6).decimal(),
+    numeric("365", "VOLUME (ft3)", 6).decimal(),
+    numeric("366", "VOLUME (yd3)", 6).decimal(),
+    numeric("367", "VOLUME (in3), log", 6).decimal(),
+    numeric("368", "VOLUME (ft3), log", 6).decimal(),
+    numeric("369", "VOLUME (yd3), log", 6).decimal(),
+    numeric_up_to("37", "COUNT", 8),
+    numeric_up_to("390", "AMOUNT", 15).decimal(),
+    numeric_up_to("391", "AMOUNT", 15).after_digits(3).amount(),
+    numeric_up_to("392", "PRICE", 15).decimal(),
+    numeric_up_to("393", "PRICE", 15).after_digits(3).amount(),
+    numeric("394", "PRCNT OFF", 4).decimal(),
+    numeric("395", "PRICE/UoM", 6).decimal(),
+    text("400", "ORDER NUMBER", 30),
+    text("401", "GINC", 30),
+    numeric("402", "GSIN", 17).check_digit(),
+    text("403", "ROUTE", 30),
+    numeric("410", "SHIP TO LOC", 13).check_digit(),
+    numeric("411", "BILL TO", 13).check_digit(),
+    numeric("412", "PURCHASE FROM", 13).check_digit(),
+    numeric("413", "SHIP FOR LOC", 13).check_digit(),
+    numeric("414", "LOC No.", 13).check_digit(),
+    numeric("415", "PAY TO", 13).check_digit(),
+    numeric("416", "PROD/SERV LOC", 13).check_digit(),
+    numeric("417", "PARTY", 13).check_digit(),
+    text("420", "SHIP TO POST", 20),
+    text("421", "SHIP TO POST", 9).after_digits(3),
+    numeric("422", "ORIGIN", 3),
+    numeric_up_to("423", "COUNTRY - INITIAL PROCESS.", 12).after_digits(3),
+    numeric("424", "COUNTRY - PROCESS.", 3),
+    numeric_up_to("425", "COUNTRY - DISASSEMBLY", 12).after_digits(3),
+    numeric("426", "COUNTRY - FULL PROCESS", 3),
+    text("427", "ORIGIN SUBDIVISION", 3),
+    text("4300", "SHIP TO COMP", 35),
+    text("4301", "SHIP TO NAME", 35),
+    text("4302", "SHIP TO ADD1", 70),
+    text("4303", "SHIP TO ADD2", 70),
+    text("4304", "SHIP TO SUB", 70),
+    text("4305", "SHIP TO LOC", 70),
+    text("4306", "SHIP TO REG", 70),
+    text_of("4307", "SHIP TO COUNTRY", 2),
+    text("4308", "SHIP TO PHONE", 30),
+    numeric("4309", "SHIP TO GEO", 20),
+    text("4310", "RTN TO COMP", 35),
+    text("4311", "RTN TO NAME", 35),
+    text("4312", "RTN TO ADD1", 70),
+    text("4313", "RTN TO ADD2", 70),
+    text("4314", "RTN TO SUB", 70),
+    text("4315", "RTN TO LOC", 70),
+    text("4316", "RTN TO REG", 70),
+    text_of("4317", "RTN TO COUNTRY", 2),
+    text("4318", "RTN TO POST", 20),
+    text("4319", "RTN TO PHONE", 30),
+    text("4320", "SRV DESCRIPTION", 35),
+    numeric("4321", "DANGEROUS GOODS", 1),
+    numeric("4322", "AUTH LEAVE", 1),
+    numeric("4323", "SIG REQUIRED", 1),
+    numeric("4324", "NBEF DEL DT.", 10),
+    numeric("4325", "NAFT DEL DT.", 10),
+    numeric("4326", "REL DATE", 6).date(),
+    numeric("7001", "NSN", 13),
+    text("7002", "MEAT CUT", 30),
+    numeric("7003", "EXPIRY TIME", 10),
+    numeric_up_to("7004", "ACTIVE POTENCY", 4),
+    text("7005", "CATCH AREA", 12),
+    numeric("7006", "FIRST FREEZE DATE", 6).date(),
+    numeric_up_to("7007", "HARVEST DATE", 6).after_digits(6),
+    text("7008", "AQUATIC SPECIES", 3),
+    text("7009", "FISHING GEAR TYPE", 10),
+    text("7010", "PROD METHOD", 2),
+    numeric_up_to("7011", "TEST BY DATE", 4).after_digits(6),
+    text("7020", "REFURB LOT", 20),
+    text("7021", "FUNC STAT", 20),
+    text("7022", "REV STAT", 20),
+    text("7023", "GIAI - ASSEMBLY", 30),
+    text("703", "PROCESSOR #", 27).after_digits(3).indexed(),
+    text_of("7040", "UIC+EXT", 4),
+    text("710", "NHRN PZN", 20),
+    text("711", "NHRN CIP", 20),
+    text("712", "NHRN CN", 20),
+    text("713", "NHRN DRN", 20),
+    text("714", "NHRN AIM", 20),
+    text("715", "NHRN NDC", 20),
+    text("723", "CERT #", 30).indexed(),
+    text("7240", "PROTOCOL", 20),
+    numeric("8001", "DIMENSIONS", 14),
+    text("8002", "CMT No.", 20),
+    text("8003", "GRAI", 16).after_digits(14).check_digit(),
+    text("8004", "GIAI", 30),
+    numeric("8005", "PRICE PER UNIT", 6),
+    numeric("8006", "ITIP", 18).check_digit_at(14),
+    text("8007", "IBAN", 34),
+    numeric_up_to("8008", "PROD TIME", 4).after_digits(8),
+    text("8009", "OPTSEN", 50),
+    text("8010", "CPID", 30),
+    numeric_up_to("8011", "CPID SERIAL", 12),
+    text("8012", "VERSION", 20),
+    text("8013", "GMN", 25),
+    numeric("8017", "GSRN - PROVIDER", 18).check_digit(),
+    numeric("8018", "GSRN - RECIPIENT", 18).check_digit(),
+    numeric_up_to("8019", "SRIN", 10),
+    text("8020", "REF No.", 25),
+    numeric("8026", "ITIP CONTENT", 18).check_digit_at(14),
+    text("8110", "COUPON", 70),
+    numeric("8111", "POINTS", 4),
+    text("8112", "COUPON", 70),
+    text("8200", "PRODUCT URL", 70),
+    text("90", "INTERNAL", 30),
+    text("91", "INTERNAL", 90),
+    text("92", "INTERNAL", 90),
+    text("93", "INTERNAL", 90),
+    text("94", "INTERNAL", 90),
+    text("95", "INTERNAL", 90),
+    text("96", "INTERNAL", 90),
+    text("97", "INTERNAL", 90),
+    text("98", "INTERNAL", 90),
+    text("99", "INTERNAL", 90),
+];
+
+#[derive(Debug)]
+/// One application identifier and its value
+pub(crate) struct Gs1Element {
+    pub(crate) ai: String,
+    pub(crate) definition: &'static AiDefinition,
+    pub(crate) value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The value of an element, interpreted according to its AI
+pub(crate) enum Gs1Value {
+    Text,
+    /// The date as a year, month and day. A day of 00 in the data means the end of the month.
+    Date(i32, u32, u32),
+    Number(f64),
+    Amount {
+        currency: String,
+        amount: f64,
+    },
+}
+
+impl Gs1Element {
+    /// The value interpreted according to the AI. `current_year` decides the century of dates.
+    pub(crate) fn typed_value(&self, current_year: i32) -> Gs1Value {
+        match self.definition.kind {
+            Kind::Plain => Gs1Value::Text,
+            Kind::Date => {
+                let number = |range: std::ops::Range<usize>| {
+                    self.value[range].parse::<u32>().unwrap_or_default()
+                };
+                let year = full_year(number(0..2) as i32, current_year);
+                let month = number(2..4);
+                let day = match number(4..6) {
+                    0 => days_in_month(year, month),
+                    day => day,
+                };
+                Gs1Value::Date(year, month, day)
+            }
+            Kind::Decimal => Gs1Value::Number(self.decimal(&self.value)),
+            Kind::Amount => Gs1Value::Amount {
+                currency: self.value[..3].to_owned(),
+                amount: self.decimal(&self.value[3..]),
+            },
+        }
+    }
+
+    fn decimal(&self, digits: &str) -> f64 {
+        let places = self.ai[3..4].parse::<i32>().unwrap_or_default();
+        digits.parse::<f64>().unwrap_or_default() / 10f64.powi(places)
+    }
+
+    /// Whether this field needs a separator when another field follows it. Only the AIs whose
+    /// length is predefined by the standard can go without one, even where the AI's own length
+    /// is fixed.
+    fn needs_separator(&self) -> bool {
+        !PREDEFINED_LENGTH.contains(&&self.ai[..2])
+    }
+}
+
+/// Join elements into an element string, putting `separator` after each field that needs one
+/// and isn't the last
+pub(crate) fn element_string(elements: &[Gs1Element], separator: char) -> String {
+    let mut text = String::new();
+    for (i, element) in elements.iter().enumerate() {
+        text.push_str(&element.ai);
+        text.push_str(&element.value);
+        if element.needs_separator() && i + 1 < elements.len() {
+            text.push(separator);
+        }
+    }
+    text
+}
+
+/// Split a decoded GS1 element string into its elements.
+///
+/// A leading symbology identifier such as "]C1" and a leading separator are skipped. Text in the
+/// bracketed "(01)..." form, which is how DataBar Expanded results are returned, is also
+/// accepted.
+pub(crate) fn parse_element_string(text: &str) -> Result<Vec<Gs1Element>, BarcodeError> {
+    let mut rest = text;
+    if rest.starts_with(']') {
+        rest = rest.get(3..).unwrap_or_default();
+    }
+    let rest = rest.trim_start_matches(GROUP_SEPARATOR);
+    if rest.starts_with('(') {
+        return parse_bracketed(rest);
+    }
+    if rest.is_empty() {
+        return Err(format_error(
+            "the text does not contain a GS1 element string",
+        ));
+    }
+
+    let mut elements = Vec::new();
+    let mut rest = rest;
+    while !rest.is_empty() {
+        let (ai, definition) = find_definition(rest)?;
+        rest = &rest[ai.len()..];
+        let value = if definition.is_fixed_length() {
+            let end = rest
+                .char_indices()
+                .nth(definition.max)
+                .map_or(rest.len(), |(index, _)| index);
+            &rest[..end]
+        } else {
+            rest.split(GROUP_SEPARATOR).next().unwrap_or_default()
+        };
+        rest = rest[value.len()..].trim_start_matches(GROUP_SEPARATOR);
+        elements.push(element(ai, definition, value.to_owned())?);
+    }
+    Ok(elements)
+}
+
+/// Split text in the human readable "(01)09501101530003(17)260101" form into its elements.
+///
+/// A bracket inside a value is written as "\(".
+pub(crate) fn parse_bracketed(text: &str) -> Result<Vec<Gs1Element>, BarcodeError> {
+    let mut elements = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(open) = chars.next() {
+        if open != '(' {
+            return Err(format_error(format!(
+                "expected \"(\" to start an application identifier, found \"{open}\""
+            )));
+        }
+        let ai: String = chars.by_ref().take_while(|c| *c != ')').collect();
+        let Some((_, definition)) = find_definition(&ai)
+            .ok()
+            .filter(|(found, _)| found.len() == ai.len())
+        else {
+            return Err(unknown_ai(&ai));
+        };
+        let mut value = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '(') {
+            if c == '\\' && chars.peek() == Some(&'(') {
+                value.push('(');
+                chars.next();
+            } else {
+                value.push(c);
+            }
+        }
+        elements.push(element(&ai, definition, value)?);
+    }
+    if elements.is_empty() {
+        return Err(format_error(
+            "the text does not contain a GS1 element string",
+        ));
+    }
+    Ok(elements)
+}
+
+/// The AI at the start of `text` and its definition
+fn find_definition(text: &str) -> Result<(&str, &'static AiDefinition), BarcodeError> {
+    DEFINITIONS
+        .iter()
+        .find_map(|definition| {
+            let length = definition.ai.len() + usize::from(definition.indexed);
+            let ai = text.get(..length)?;
+            let matches = if definition.indexed {
+                ai.starts_with(definition.ai) && ai.as_bytes()[3].is_ascii_digit()
+            } else {
+                ai == definition.ai
+            };
+            matches.then_some((ai, definition))
+        })
+        .ok_or_else(|| {
+            let start: String = text.chars().take(4).collect();
+            unknown_ai(&start)
+        })
+}
+
+/// Check `value` against the definition of `ai`
+fn element(
+    ai: &str,
+    definition: &'static AiDefinition,
+    value: String,
+) -> Result<Gs1Element, BarcodeError> {
+    // every character GS1 allows is ASCII, so past here a character is a byte
+    if !value.is_ascii() {
+        return Err(not_allowed(ai, &value));
+    }
+    let length = value.len();
+    if length < definition.min || length > definition.max {
+        let expected = if definition.is_fixed_length() {
+            format!("{}", definition.max)
+        } else {
+            format!("{} to {}", definition.min.max(1), definition.max)
+        };
+        return Err(format_error(format!(
+            "the value of AI ({ai}) must be {expected} characters long, found \"{value}\""
+        )));
+    }
+    let digits = definition.digits.min(length);
+    let (leading, rest) = value.split_at(digits);
+    let valid = leading.bytes().all(|b| b.is_ascii_digit())
+        && match definition.content {
+            Content::Numeric => rest.bytes().all(|b| b.is_ascii_digit()),
+            Content::Text => rest.bytes().all(is_cset82),
+        };
+    if !valid {
+        return Err(not_allowed(ai, &value));
+    }
+    if definition.check_digit && !has_valid_check_digit(leading) {
+        return Err(BarcodeError::new(
+            ErrorKind::Checksum,
+            format!("the check digit of AI ({ai}) is wrong: \"{value}\""),
+        ));
+    }
+    if definition.kind == Kind::Date {
+        let number = |range: std::ops::Range<usize>| value[range].parse().unwrap_or_default();
+        let (year, month, day) = (number(0..2), number(2..4), number(4..6));
+        // every century the two digit year can fall in starts with a leap year, except 2100
+        if !(1..=12).contains(&month) || day > days_in_month(2000 + year as i32, month) {
+            return Err(format_error(format!(
+                "the value of AI ({ai}) is not a YYMMDD date: \"{value}\""
+            )));
+        }
+    }
+    Ok(Gs1Element {
+        ai: ai.to_owned(),
+        definition,
+        value,
+    })
+}
+
+/// Whether the last of `digits` is the GS1 mod 10 check digit of the others
+fn has_valid_check_digit(digits: &str) -> bool {
+    let Some((&check, data)) = digits.as_bytes().split_last() else {
+        return false;
+    };
+    // weights alternate 3, 1, 3... from the digit next to the check digit
+    let sum: u32 = data
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| u32::from(digit - b'0') * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    (10 - sum % 10) % 10 == u32::from(check - b'0')
+}
+
+/// The GS1 AI encodable character set 82
+fn is_cset82(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!\"%&'()*+,-./:;<=>?_".contains(&b)
+}
+
+/// The four digit year of a two digit one, within 49 years before or 50 years after
+/// `current_year`, as the GS1 General Specifications define it
+fn full_year(year: i32, current_year: i32) -> i32 {
+    let century = current_year - current_year.rem_euclid(100);
+    match year - current_year.rem_euclid(100) {
+        51.. => century - 100 + year,
+        ..=-50 => century + 100 + year,
+        _ => century + year,
+    }
+}
+
+pub(crate) fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+fn unknown_ai(ai: &str) -> BarcodeError {
+    format_error(format!("unknown GS1 application identifier at \"{ai}\""))
+}
+
+fn not_allowed(ai: &str, value: &str) -> BarcodeError {
+    format_error(format!(
+        "the value of AI ({ai}) contains characters it does not allow: \"{value}\""
+    ))
+}
+
+fn format_error(message: impl Into<String>) -> BarcodeError {
+    BarcodeError::new(ErrorKind::Format, message)
+}
+
+#[wasm_bindgen]
+impl BarcodeResult {
+    #[wasm_bindgen(unchecked_return_type = "Gs1Element[]")]
+    /// Split the text of a GS1-128, GS1 DataMatrix, GS1 QR or DataBar Expanded result into its
+    /// application identifiers, in the order they appear. Decode with the `AssumeGs1` hint so
+    /// the separators between variable length fields are kept.
+    ///
+    /// Fails with a `Format` error if the text contains an unknown AI or a value that does not
+    /// match its AI, and with a `Checksum` error if a GTIN, SSCC, GLN or other check digit is
+    /// wrong.
+    pub fn gs1_elements(&self) -> Result<js_sys::Array, BarcodeError> {
+        let current_year = js_sys::Date::new_0().get_utc_full_year() as i32;
+        Ok(parse_element_string(&self.text)?
+            .iter()
+            .map(|element| {
+                let object = js_sys::Object::new();
+                set(&object, "ai", element.ai.as_str().into());
+                set(&object, "title", element.definition.title.into());
+                set(&object, "value", element.value.as_str().into());
+                match element.typed_value(current_year) {
+                    Gs1Value::Text => {}
+                    Gs1Value::Date(year, month, day) => {
+                        let milliseconds = days_since_epoch(year, month, day) as f64 * 86_400_000.0;
+                        let date = js_sys::Date::new(&JsValue::from_f64(milliseconds));
+                        set(&object, "date", date.into());
+                    }
+                    Gs1Value::Number(number) => set(&object, "number", number.into()),
+                    Gs1Value::Amount { currency, amount } => {
+                        set(&object, "number", amount.into());
+                        set(&object, "currency", currency.into());
+                    }
+                }
+                JsValue::from(object)
+            })
+            .collect())
+    }
+}
+
+/// Days from 1970-01-01 to the given date in the proleptic Gregorian calendar
+pub(crate) fn days_since_epoch(year: i32, month: u32, day: u32) -> i64 {
+    // count years from March so the leap day falls at the end
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(elements: &[Gs1Element]) -> Vec<(&str, &str)> {
+        elements
+            .iter()
+            .map(|element| (element.ai.as_str(), element.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_definition_of_an_ai() {
+        let (ai, definition) = find_definition("0109501101530003").unwrap();
+        assert_eq!((ai, definition.title), ("01", "GTIN"));
+        let (ai, definition) = find_definition("8020ABC").unwrap();
+        assert_eq!((ai, definition.title), ("8020", "REF No."));
+        // the last digit of an indexed AI is part of it
+        let (ai, definition) = find_definition("3103001250").unwrap();
+        assert_eq!((ai, definition.title), ("3103", "NET WEIGHT (kg)"));
+    }
+
+    #[test]
+    fn rejects_an_unknown_ai() {
+        let error = find_definition("0512345").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+        assert!(error.message().contains("\"0512\""));
+        // too short to hold the whole AI
+        assert!(find_definition("310").is_err());
+    }
+
+    #[test]
+    fn validates_check_digits() {
+        assert!(has_valid_check_digit("09501101530003"));
+        assert!(has_valid_check_digit("106141410000001238"));
+        assert!(!has_valid_check_digit("09501101530004"));
+        assert!(!has_valid_check_digit(""));
+    }
+
+    #[test]
+    fn rejects_a_wrong_check_digit() {
+        let error = parse_bracketed("(01)09501101530004").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Checksum);
+        // the check digit of a GDTI ends its leading digits rather than the value
+        assert!(parse_bracketed("(253)9501101530010ABC").is_ok());
+        let error = parse_bracketed("(253)9501101530011ABC").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Checksum);
+    }
+
+    #[test]
+    fn splits_an_element_string_at_separators() {
+        let elements =
+            parse_element_string("]C1010950110153000310AB-123\u{1d}1726010121XYZ99").unwrap();
+        assert_eq!(
+            values(&elements),
+            [
+                ("01", "09501101530003"),
+                ("10", "AB-123"),
+                ("17", "260101"),
+                ("21", "XYZ99"),
+            ]
+        );
+        // some readers return the leading FNC1 as a separator rather than an identifier
+        let elements = parse_element_string("\u{1d}0109501101530003").unwrap();
+        assert_eq!(values(&elements), [("01", "09501101530003")]);
+    }
+
+    #[test]
+    fn a_variable_length_field_without_a_separator_runs_to_the_end() {
+        let elements = parse_element_string("10AB1726010121XYZ").unwrap();
+        assert_eq!(values(&elements), [("10", "AB1726010121XYZ")]);
+    }
+
+    #[test]
+    fn rejects_malformed_element_strings() {
+        // cut short inside a fixed length field
+        let error = parse_element_string("01095011015300").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+        // no month 13
+        let error = parse_element_string("17261301").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+        // a GTIN is all digits
+        let error = parse_element_string("010950110153000A").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+        // a separator is not in the character set
+        let error = parse_bracketed("(10)AB\u{1d}C").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Format);
+        assert!(parse_element_string("").is_err());
+        assert!(parse_element_string("]d2").is_err());
+    }
+
+    #[test]
+    fn rejects_non_ascii_values_without_panicking() {
+        for error in [
+            parse_bracketed("(253)ééééééééééééé").unwrap_err(),
+            parse_element_string("253ééééééééééééé").unwrap_err(),
+            parse_bracketed("(10)lot—1").unwrap_err(),
+            parse_element_string("0109501101530é03").unwrap_err(),
+        ] {
+            assert_eq!(error.kind(), ErrorKind::Format);
+        }
+    }
+
+    #[test]
+    fn parses_and_joins_the_bracketed_form() {
+        let elements = parse_bracketed("(01)09501101530003(10)A\\(1)(17)260101").unwrap();
+        assert_eq!(
+            values(&elements),
+            [("01", "09501101530003"), ("10", "A(1)"), ("17", "260101")]
+        );
+        assert_eq!(
+            element_string(&elements, GROUP_SEPARATOR),
+            "010950110153000310A(1)\u{1d}17260101"
+        );
+        assert!(parse_bracketed("01)09501101530003").is_err());
+        assert!(parse_bracketed("(0)1").is_err());
+    }
+
+    #[test]
+    fn no_separator_after_a_predefined_length_or_the_last_field() {
+        let elements = parse_bracketed("(17)260101(10)ABC(21)XYZ").unwrap();
+        assert_eq!(element_string(&elements, '|'), "1726010110ABC|21XYZ");
+    }
+
+    #[test]
+    fn types_values_by_ai() {
+        let elements = parse_bracketed("(17)240200(3103)001250(3932)9781999(10)ABC").unwrap();
+        let typed: Vec<Gs1Value> = elements
+            .iter()
+            .map(|element| element.typed_value(2026))
+            .collect();
+        assert_eq!(
+            typed,
+            [
+                // a day of 00 is the end of the month
+                Gs1Value::Date(2024, 2, 29),
+                Gs1Value::Number(1.25),
+                Gs1Value::Amount {
+                    currency: "978".to_owned(),
+                    amount: 19.99
+                },
+                Gs1Value::Text,
+            ]
+        );
+    }
+
+    #[test]
+    fn two_digit_years_fall_near_the_current_year() {
+        assert_eq!(full_year(26, 2026), 2026);
+        assert_eq!(full_year(76, 2026), 2076);
+        assert_eq!(full_year(77, 2026), 1977);
+        assert_eq!(full_year(1, 2090), 2101);
+        assert_eq!(full_year(41, 2090), 2041);
+        assert_eq!(full_year(40, 2090), 2140);
+    }
+
+    #[test]
+    fn counts_days_since_the_epoch() {
+        assert_eq!(days_since_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_epoch(2000, 3, 1), 11_017);
+        assert_eq!(days_since_epoch(1969, 12, 31), -1);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+    }
+}
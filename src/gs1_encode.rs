@@ -0,0 +1,426 @@
+use rxing::common::BitMatrix;
+use rxing::datamatrix::encoder::{
+    error_correction, DefaultPlacement, SymbolInfo, SymbolInfoLookup, SymbolShapeHint,
+};
+use rxing::EncodeHints;
+
+use crate::encode_limits;
+use crate::error::{BarcodeError, ErrorKind};
+use crate::gs1::{self, GROUP_SEPARATOR};
+use crate::BarcodeFormat;
+
+/// How rxing's Code 128 writer is told to write FNC1
+const CODE_128_FNC1: char = '\u{00f1}';
+
+/// The Data Matrix FNC1 codeword
+const DATA_MATRIX_FNC1: u8 = 232;
+
+/// The Data Matrix codeword that ends the data before the padding
+const DATA_MATRIX_PAD: u8 = 129;
+
+/// Encode text in the human readable "(01)09501101530003(17)260101" form as a GS1 symbol.
+///
+/// Every AI and value is checked, then the fields are joined with FNC1 after each variable
+/// length field that isn't last, and a leading FNC1 marks the symbol as GS1.
+pub(crate) fn encode(
+    data: &str,
+    width: u32,
+    height: u32,
+    format: BarcodeFormat,
+    hints: &EncodeHints,
+) -> Result<BitMatrix, BarcodeError> {
+    // the input is an argument here rather than something read from a symbol
+    let elements = gs1::parse_bracketed(data)
+        .map_err(|error| BarcodeError::illegal_argument(error.message()))?;
+    match format {
+        BarcodeFormat::Code128 => {
+            let mut contents = String::from(CODE_128_FNC1);
+            contents.push_str(&gs1::element_string(&elements, CODE_128_FNC1));
+            crate::write_bit_matrix(&contents, width, height, format, hints)
+        }
+        // rxing adds the FNC1 mode indicator and the separators stay as they are
+        BarcodeFormat::QrCode => crate::write_bit_matrix(
+            &gs1::element_string(&elements, GROUP_SEPARATOR),
+            width,
+            height,
+            format,
+            hints,
+        ),
+        BarcodeFormat::DataMatrix => {
+            encode_limits::check_size(width, height, hints)?;
+            data_matrix(
+                &gs1::element_string(&elements, GROUP_SEPARATOR),
+                width,
+                height,
+                hints,
+            )
+        }
+        _ => Err(BarcodeError::new(
+            ErrorKind::UnsupportedFormat,
+            "GS1 element strings can only be encoded as Code 128, Data Matrix or QR Code",
+        )),
+    }
+}
+
+/// Only rxing's minimal Data Matrix encoder follows `Gs1Format`, and it stores each FNC1 as a
+/// value it then mistakes for an ECI, which overflows in debug builds and drops the separators
+/// in release builds. So the codewords are built here in ASCII encodation, which GS1 data suits,
+/// and handed to rxing for error correction and placement. rxing keeps its symbol layout
+/// private, so that is repeated here too.
+#[allow(deprecated)]
+fn data_matrix(
+    element_string: &str,
+    width: u32,
+    height: u32,
+    hints: &EncodeHints,
+) -> Result<BitMatrix, BarcodeError> {
+    let mut codewords = vec![DATA_MATRIX_FNC1];
+    let mut chars = element_string.chars().peekable();
+    while let Some(c) = chars.next() {
+        let pair = c
+            .to_digit(10)
+            .zip(chars.peek().and_then(|next| next.to_digit(10)));
+        match (c, pair) {
+            (GROUP_SEPARATOR, _) => codewords.push(DATA_MATRIX_FNC1),
+            // two digits share a codeword
+            (_, Some((tens, ones))) => {
+                chars.next();
+                codewords.push(130 + (tens * 10 + ones) as u8);
+            }
+            // the GS1 character set is all ASCII
+            _ => codewords.push(c as u8 + 1),
+        }
+    }
+
+    let lookup = SymbolInfoLookup::new();
+    let symbol = lookup
+        .lookup_with_codewords_shape_size_fail(
+            codewords.len() as u32,
+            hints.DataMatrixShape.unwrap_or(SymbolShapeHint::FORCE_NONE),
+            &hints.MinSize,
+            &hints.MaxSize,
+            false,
+        )?
+        .ok_or_else(|| {
+            BarcodeError::new(
+                ErrorKind::EncodeCapacityExceeded,
+                format!(
+                    "the data needs {} codewords, more than a Data Matrix symbol of the requested shape and size holds",
+                    codewords.len()
+                ),
+            )
+        })?;
+
+    // pad with the 253-state algorithm of ISO/IEC 16022 annex B.1
+    let capacity = symbol.getDataCapacity() as usize;
+    if codewords.len() < capacity {
+        codewords.push(DATA_MATRIX_PAD);
+    }
+    while codewords.len() < capacity {
+        let position = codewords.len() as u32 + 1;
+        let pad = 129 + (149 * position) % 253 + 1;
+        codewords.push(if pad <= 254 { pad } else { pad - 254 } as u8);
+    }
+
+    let codewords: String = codewords.into_iter().map(char::from).collect();
+    let codewords = error_correction::encodeECC200(&codewords, symbol)?;
+    let mut placement = DefaultPlacement::new(
+        codewords,
+        symbol.getSymbolDataWidth()? as usize,
+        symbol.getSymbolDataHeight()? as usize,
+    );
+    placement.place()?;
+
+    let modules = data_matrix_modules(&placement, symbol)?;
+    let margin = hints
+        .Margin
+        .as_deref()
+        .and_then(|margin| margin.parse().ok())
+        .unwrap_or(0);
+    scale(&modules, width, height, margin).map_err(BarcodeError::from)
+}
+
+/// The placed data surrounded by the finder and timing patterns of each data region, as rxing's
+/// Data Matrix writer lays them out
+fn data_matrix_modules(
+    placement: &DefaultPlacement,
+    symbol: &SymbolInfo,
+) -> Result<BitMatrix, rxing::Exceptions> {
+    let (data_width, data_height) = (symbol.getSymbolDataWidth()?, symbol.getSymbolDataHeight()?);
+    let (symbol_width, symbol_height) = (symbol.getSymbolWidth()?, symbol.getSymbolHeight()?);
+    // each data region is framed by two modules in each direction
+    let region_width = data_width / ((symbol_width - data_width) / 2);
+    let region_height = data_height / ((symbol_height - data_height) / 2);
+
+    let mut matrix = BitMatrix::new(symbol_width, symbol_height)?;
+    let mut matrix_y = 0;
+    for y in 0..data_height {
+        // alternating timing pattern along the top of each region
+        if y % region_height == 0 {
+            for x in (0..symbol_width).step_by(2) {
+                matrix.set(x, matrix_y);
+            }
+            matrix_y += 1;
+        }
+        let mut matrix_x = 0;
+        for x in 0..data_width {
+            // solid finder pattern down the left of each region
+            if x % region_width == 0 {
+                matrix.set(matrix_x, matrix_y);
+                matrix_x += 1;
+            }
+            if placement.getBit(x as usize, y as usize) {
+                matrix.set(matrix_x, matrix_y);
+            }
+            matrix_x += 1;
+            // alternating timing pattern down the right
+            if x % region_width == region_width - 1 {
+                if y % 2 == 0 {
+                    matrix.set(matrix_x, matrix_y);
+                }
+                matrix_x += 1;
+            }
+        }
+        matrix_y += 1;
+        // solid finder pattern along the bottom
+        if y % region_height == region_height - 1 {
+            for x in 0..symbol_width {
+                matrix.set(x, matrix_y);
+            }
+            matrix_y += 1;
+        }
+    }
+    Ok(matrix)
+}
+
+/// Scale `modules` by the largest whole number that fits the requested size with the margin,
+/// centered, as rxing's Data Matrix writer does. A requested size smaller than the symbol gives
+/// the symbol unscaled, and one too small for the margin grows to fit it.
+fn scale(
+    modules: &BitMatrix,
+    width: u32,
+    height: u32,
+    margin: u32,
+) -> Result<BitMatrix, rxing::Exceptions> {
+    let (modules_width, modules_height) = (modules.getWidth(), modules.getHeight());
+    if width < modules_width || height < modules_height {
+        return Ok(modules.clone());
+    }
+    let padded_width = modules_width + margin * 2;
+    let padded_height = modules_height + margin * 2;
+    let (output_width, output_height) = (width.max(padded_width), height.max(padded_height));
+    let multiple = (output_width / padded_width).min(output_height / padded_height);
+    let left = (output_width - modules_width * multiple) / 2;
+    let top = (output_height - modules_height * multiple) / 2;
+
+    let mut output = BitMatrix::new(output_width, output_height)?;
+    for y in 0..modules_height {
+        for x in 0..modules_width {
+            if modules.get(x, y) {
+                output.setRegion(left + x * multiple, top + y * multiple, multiple, multiple)?;
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rxing::common::HybridBinarizer;
+    use rxing::{
+        BinaryBitmap, DecodeHints, Luma8LuminanceSource, RXingResult, RXingResultMetadataType,
+        RXingResultMetadataValue, Reader, Writer,
+    };
+
+    /// A fixed length field, a variable length one that needs a separator after it, and a last
+    /// variable length one that doesn't
+    const ELEMENTS: &str = "(01)09501101530003(10)AB-123(17)260101(21)XYZ99";
+
+    /// The element string a reader returns for `ELEMENTS`, with GS in place of FNC1
+    const ELEMENT_STRING: &str = "010950110153000310AB-123\u{1d}1726010121XYZ99";
+
+    /// The hints `encode` is reached with
+    fn gs1_hints() -> EncodeHints {
+        EncodeHints {
+            Gs1Format: Some(true),
+            ..Default::default()
+        }
+    }
+
+    /// Read `matrix` with `reader`, surrounded by a quiet zone
+    fn read(matrix: &BitMatrix, mut reader: impl Reader, hints: &DecodeHints) -> RXingResult {
+        const QUIET_ZONE: u32 = 10;
+        let width = matrix.getWidth() + QUIET_ZONE * 2;
+        let height = matrix.getHeight() + QUIET_ZONE * 2;
+        let mut luma = vec![255u8; (width * height) as usize];
+        for y in 0..matrix.getHeight() {
+            for x in 0..matrix.getWidth() {
+                if matrix.get(x, y) {
+                    luma[((y + QUIET_ZONE) * width + x + QUIET_ZONE) as usize] = 0;
+                }
+            }
+        }
+        let source = Luma8LuminanceSource::new(luma, width, height);
+        let mut image = BinaryBitmap::new(HybridBinarizer::new(source));
+        reader.decode_with_hints(&mut image, hints).unwrap()
+    }
+
+    fn symbology_identifier(result: &RXingResult) -> String {
+        match result
+            .getRXingResultMetadata()
+            .get(&RXingResultMetadataType::SYMBOLOGY_IDENTIFIER)
+        {
+            Some(RXingResultMetadataValue::SymbologyIdentifier(identifier)) => identifier.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// Encode `data` as a Data Matrix symbol at one pixel per module and read it back
+    fn data_matrix_round_trip(data: &str, hints: &EncodeHints) -> (BitMatrix, RXingResult) {
+        // a requested size smaller than the symbol leaves it unscaled
+        let modules = encode(data, 1, 1, BarcodeFormat::DataMatrix, hints).unwrap();
+        let scaled = scale(&modules, modules.getWidth() * 4, modules.getHeight() * 4, 0).unwrap();
+        let result = read(
+            &scaled,
+            rxing::datamatrix::DataMatrixReader,
+            &DecodeHints::default(),
+        );
+        (modules, result)
+    }
+
+    #[test]
+    fn data_matrix_is_read_back_as_gs1() {
+        let (modules, result) = data_matrix_round_trip(ELEMENTS, &gs1_hints());
+        assert_eq!(modules.getWidth(), modules.getHeight());
+        assert_eq!(result.getText(), ELEMENT_STRING);
+        assert_eq!(symbology_identifier(&result), "]d2");
+    }
+
+    #[test]
+    fn data_matrix_with_several_data_regions_is_read_back() {
+        // 52 codewords need a 32x32 symbol, made of 2x2 regions
+        let data = "(01)09501101530003(10)ABCDEFGHIJKLMNOPQRST(21)abcdefghijklmnopqrst";
+        let (modules, result) = data_matrix_round_trip(data, &gs1_hints());
+        assert_eq!((modules.getWidth(), modules.getHeight()), (32, 32));
+        assert_eq!(
+            result.getText(),
+            "010950110153000310ABCDEFGHIJKLMNOPQRST\u{1d}21abcdefghijklmnopqrst"
+        );
+        assert_eq!(symbology_identifier(&result), "]d2");
+    }
+
+    #[test]
+    fn rectangular_data_matrix_is_read_back() {
+        let hints = EncodeHints {
+            DataMatrixShape: Some(SymbolShapeHint::FORCE_RECTANGLE),
+            ..gs1_hints()
+        };
+        let (modules, result) = data_matrix_round_trip(ELEMENTS, &hints);
+        assert!(modules.getWidth() > modules.getHeight());
+        assert_eq!(result.getText(), ELEMENT_STRING);
+        assert_eq!(symbology_identifier(&result), "]d2");
+    }
+
+    #[test]
+    fn rectangular_data_matrix_with_two_data_regions_is_read_back() {
+        let hints = EncodeHints {
+            DataMatrixShape: Some(SymbolShapeHint::FORCE_RECTANGLE),
+            ..gs1_hints()
+        };
+        // 30 codewords need a 16x36 symbol, made of two regions side by side
+        let data = "(01)09501101530003(10)ABCDEFGH(21)01234567890123456789";
+        let (modules, result) = data_matrix_round_trip(data, &hints);
+        assert_eq!((modules.getWidth(), modules.getHeight()), (36, 16));
+        assert_eq!(
+            result.getText(),
+            "010950110153000310ABCDEFGH\u{1d}2101234567890123456789"
+        );
+    }
+
+    #[test]
+    fn data_matrix_is_scaled_and_centered() {
+        let modules = encode(ELEMENTS, 1, 1, BarcodeFormat::DataMatrix, &gs1_hints()).unwrap();
+        let size = modules.getWidth();
+        let scaled = encode(
+            ELEMENTS,
+            size * 3 + 1,
+            size * 3 + 1,
+            BarcodeFormat::DataMatrix,
+            &gs1_hints(),
+        )
+        .unwrap();
+        assert_eq!(scaled.getWidth(), size * 3 + 1);
+        // the finder pattern's corner lands on the first scaled module, offset by the spare pixel
+        assert!(!scaled.get(0, size * 3));
+        assert!(scaled.get(0, size * 3 - 1));
+    }
+
+    #[test]
+    fn code_128_is_read_back_as_gs1() {
+        let matrix = encode(ELEMENTS, 0, 40, BarcodeFormat::Code128, &gs1_hints()).unwrap();
+        let hints = DecodeHints {
+            AssumeGs1: Some(true),
+            ..Default::default()
+        };
+        let result = read(&matrix, rxing::oned::Code128Reader, &hints);
+        assert_eq!(result.getText(), format!("]C1{ELEMENT_STRING}"));
+        assert_eq!(symbology_identifier(&result), "]C1");
+    }
+
+    #[test]
+    fn qr_code_is_read_back_as_gs1() {
+        let matrix = encode(ELEMENTS, 200, 200, BarcodeFormat::QrCode, &gs1_hints()).unwrap();
+        let result = read(
+            &matrix,
+            rxing::qrcode::QRCodeReader,
+            &DecodeHints::default(),
+        );
+        assert_eq!(result.getText(), ELEMENT_STRING);
+        assert_eq!(symbology_identifier(&result), "]Q3");
+    }
+
+    /// When this fails rxing can write GS1 Data Matrix itself, and `data_matrix` can go
+    #[test]
+    fn rxing_still_cannot_write_gs1_data_matrix() {
+        let contents =
+            gs1::element_string(&gs1::parse_bracketed(ELEMENTS).unwrap(), GROUP_SEPARATOR);
+        for compact in [false, true] {
+            let hints = EncodeHints {
+                DataMatrixCompact: Some(compact),
+                ..gs1_hints()
+            };
+            let read_back = std::panic::catch_unwind(|| {
+                let matrix = rxing::MultiFormatWriter
+                    .encode_with_hints(
+                        &contents,
+                        &rxing::BarcodeFormat::DATA_MATRIX,
+                        100,
+                        100,
+                        &hints,
+                    )
+                    .ok()?;
+                let result = read(
+                    &matrix,
+                    rxing::datamatrix::DataMatrixReader,
+                    &DecodeHints::default(),
+                );
+                Some((result.getText().to_owned(), symbology_identifier(&result)))
+            });
+            let works = matches!(
+                read_back,
+                Ok(Some((text, identifier))) if text == ELEMENT_STRING && identifier == "]d2"
+            );
+            assert!(
+                !works,
+                "rxing now writes GS1 Data Matrix, compact: {compact}"
+            );
+        }
+    }
+
+    #[test]
+    fn other_formats_are_rejected() {
+        let error = encode(ELEMENTS, 0, 0, BarcodeFormat::AZTEC, &gs1_hints()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnsupportedFormat);
+    }
+}
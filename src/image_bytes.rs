@@ -0,0 +1,37 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageDecoder, ImageReader};
+
+use crate::error::{BarcodeError, ErrorKind};
+
+/// Decode an encoded image file (PNG, JPEG, GIF, WebP or BMP) into luma 8 pixels.
+///
+/// The container format is detected from the file's contents and any EXIF orientation is
+/// applied, so the result matches what an image viewer would show. Returns the luma data
+/// along with the width and height of the oriented image.
+pub(crate) fn image_bytes_to_luma(bytes: &[u8]) -> Result<(Vec<u8>, u32, u32), BarcodeError> {
+    let reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|err| BarcodeError::invalid_input(format!("couldn't read image: {err}")))?;
+    if reader.format().is_none() {
+        return Err(BarcodeError::new(
+            ErrorKind::UnsupportedFormat,
+            "unrecognized image format",
+        ));
+    }
+
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|err| BarcodeError::invalid_input(format!("couldn't decode image: {err}")))?;
+    // a broken EXIF block shouldn't stop the image from decoding
+    let orientation = decoder.orientation().ok();
+    let mut image = DynamicImage::from_decoder(decoder)
+        .map_err(|err| BarcodeError::invalid_input(format!("couldn't decode image: {err}")))?;
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+
+    let rgba = image.into_rgba8();
+    let (width, height) = rgba.dimensions();
+    Ok((crate::rgba_to_luma(rgba.as_raw()), width, height))
+}
@@ -1,14 +1,68 @@
+mod aamva;
+#[cfg(feature = "decode_hints")]
+mod async_decode;
+#[cfg(feature = "decode_hints")]
+mod barcode_detector;
+mod binarizer;
+mod bit_matrix;
+mod deadline;
 #[cfg(feature = "decode_hints")]
 mod decode_hints;
+#[cfg(feature = "decode_hints")]
+mod decoder;
 mod encode_hints;
+mod encode_limits;
+mod error;
+mod geometry;
+mod gs1;
+mod gs1_encode;
+#[cfg(feature = "image_decode")]
+mod image_bytes;
+mod luma_source;
+#[cfg(feature = "parsed_results")]
+mod parsed_result;
+mod pixel_format;
+mod reader;
+mod region;
+mod render;
+mod result_metadata;
+#[cfg(feature = "decode_hints")]
+mod scanner;
+#[cfg(feature = "decode_hints")]
+mod tracker;
+#[cfg(feature = "decode_hints")]
+mod yuv;
 
-use std::collections::HashMap;
-
+use binarizer::{Binarizers, DEFAULT_FIXED_THRESHOLD};
+use deadline::{Deadline, DecodeLimits};
 use encode_hints::EncodeHintDictionary;
+use encode_limits::MAX_ENCODE_DIMENSION;
+use luma_source::SharedLumaSource;
+use reader::DiagnosticReader;
 use rxing::{self, DecodeHints, EncodeHints, ResultPoint};
 use rxing::{Reader, Writer};
 use wasm_bindgen::prelude::*;
 
+pub use aamva::AamvaRecord;
+#[cfg(feature = "decode_hints")]
+pub use barcode_detector::BarcodeDetector;
+pub use binarizer::Binarizer;
+pub use bit_matrix::BitMatrix;
+#[cfg(feature = "decode_hints")]
+pub use decoder::Decoder;
+pub use error::{BarcodeError, ErrorKind};
+pub use pixel_format::PixelFormat;
+pub use region::Region;
+#[cfg(feature = "png_output")]
+pub use render::PngColorType;
+pub use render::{RenderOptions, RgbaImage};
+#[cfg(feature = "decode_hints")]
+pub use scanner::{Scanner, ScannerOptions};
+#[cfg(feature = "decode_hints")]
+pub use tracker::{Tracker, TrackerOptions};
+#[cfg(feature = "decode_hints")]
+pub use yuv::YuvLayout;
+
 #[wasm_bindgen]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 /// Available barcode types
@@ -139,10 +193,25 @@ pub struct BarcodeResult {
     num_bits: usize,
     result_points: Vec<f32>,
     format: BarcodeFormat,
-    result_metadata: HashMap<String, String>,
+    result_metadata: rxing::RXingResultMetaDataDictionary,
     timestamp: isize,
 }
 
+impl BarcodeResult {
+    fn corners(&self) -> Option<[(f32, f32); 4]> {
+        geometry::corners(self.format, &self.result_points)
+    }
+
+    #[cfg(feature = "decode_hints")]
+    /// Move every result point by (dx, dy), used to map points found in a crop back to the full image
+    fn translate(&mut self, dx: f32, dy: f32) {
+        for point in self.result_points.chunks_exact_mut(2) {
+            point[0] += dx;
+            point[1] += dy;
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl BarcodeResult {
     pub fn timestamp(&self) -> isize {
@@ -158,6 +227,36 @@ impl BarcodeResult {
         self.result_points.to_vec()
     }
 
+    /// The symbol's corners as four (x,y) pairs, clockwise from its own top-left corner, so the
+    /// order follows the symbol as it rotates. Linear barcodes are only located along the row
+    /// that was scanned, and get a quad two pixels thick around it. Empty if the reader reported
+    /// no points.
+    pub fn corner_points(&self) -> Vec<f32> {
+        self.corners()
+            .map(|corners| corners.iter().flat_map(|(x, y)| [*x, *y]).collect())
+            .unwrap_or_default()
+    }
+
+    /// The smallest whole pixel `Region` containing the corners, ready for cropping or for
+    /// searching the same area of the next frame
+    pub fn bounding_box(&self) -> Option<Region> {
+        self.corners().as_ref().map(geometry::bounding_box)
+    }
+
+    /// The (x,y) center of the corners
+    pub fn center(&self) -> Option<Vec<f32>> {
+        self.corners().as_ref().map(|corners| {
+            let (x, y) = geometry::center(corners);
+            vec![x, y]
+        })
+    }
+
+    /// The angle of the symbol's top edge in degrees, from -180 to 180. Positive angles turn
+    /// clockwise on screen. For linear barcodes it is the direction the bars were read in.
+    pub fn rotation(&self) -> Option<f32> {
+        self.corners().as_ref().map(geometry::rotation)
+    }
+
     pub fn num_bits(&self) -> usize {
         self.num_bits
     }
@@ -170,12 +269,14 @@ impl BarcodeResult {
         self.text.to_owned()
     }
 
+    /// Every metadata entry as a string, keyed by its name, such as "Orientation". The typed
+    /// getters such as `orientation` and `byte_segments` return the same values unconverted.
     pub fn get_meta_data(&self) -> js_sys::Map {
         let output_map = js_sys::Map::new();
         for (k, v) in &self.result_metadata {
             output_map.set(
-                &wasm_bindgen::JsValue::from(v),
-                &wasm_bindgen::JsValue::from(k),
+                &wasm_bindgen::JsValue::from(get_result_metadata_name(k)),
+                &wasm_bindgen::JsValue::from(get_result_metadata_value(v)),
             );
         }
         output_map
@@ -245,16 +346,41 @@ impl From<rxing::RXingResult> for BarcodeResult {
                 .flat_map(|rxp| [rxp.get_x(), rxp.get_y()])
                 .collect(),
             format: value.getBarcodeFormat().to_owned().into(),
-            result_metadata: value
-                .getRXingResultMetadata()
-                .iter()
-                .map(|(k, v)| (get_result_metadata_name(k), get_result_metadata_value(v)))
-                .collect::<HashMap<String, String>>(),
+            result_metadata: value.getRXingResultMetadata().clone(),
             timestamp: value.getTimestamp() as isize,
         }
     }
 }
 
+fn encode_bit_matrix(
+    data: &str,
+    width: u32,
+    height: u32,
+    bc_type: BarcodeFormat,
+    hints: &EncodeHints,
+) -> Result<rxing::common::BitMatrix, BarcodeError> {
+    if matches!(hints.Gs1Format, Some(true)) && data.starts_with('(') {
+        return gs1_encode::encode(data, width, height, bc_type, hints);
+    }
+    write_bit_matrix(data, width, height, bc_type, hints)
+}
+
+/// Encode `data` as it is with rxing's writers
+fn write_bit_matrix(
+    data: &str,
+    width: u32,
+    height: u32,
+    bc_type: BarcodeFormat,
+    hints: &EncodeHints,
+) -> Result<rxing::common::BitMatrix, BarcodeError> {
+    encode_limits::check_encode(data, width, height, bc_type, hints)?;
+
+    let writer = rxing::MultiFormatWriter;
+    writer
+        .encode_with_hints(data, &bc_type.into(), width as i32, height as i32, hints)
+        .map_err(|error| BarcodeError::from_encode(error, bc_type))
+}
+
 #[wasm_bindgen]
 /// Encode a barcode with the given data, dimensions, and type
 pub fn encode_barcode(
@@ -262,17 +388,8 @@ pub fn encode_barcode(
     width: u32,
     height: u32,
     bc_type: BarcodeFormat,
-) -> Result<String, String> {
-    let writer = rxing::MultiFormatWriter;
-    let Ok(bit_matrix) = writer.encode_with_hints(
-        data,
-        &bc_type.into(),
-        width as i32,
-        height as i32,
-        &EncodeHints::default(),
-    ) else {
-        return Err("couldn't encode".to_owned());
-    };
+) -> Result<String, BarcodeError> {
+    let bit_matrix = encode_bit_matrix(data, width, height, bc_type, &EncodeHints::default())?;
     Ok(bit_matrix.to_string())
 }
 
@@ -284,20 +401,239 @@ pub fn encode_barcode_with_hints(
     height: u32,
     bc_type: BarcodeFormat,
     hints: &mut EncodeHintDictionary,
-) -> Result<String, String> {
-    let writer = rxing::MultiFormatWriter;
-    let Ok(bit_matrix) = writer.encode_with_hints(
-        data,
-        &bc_type.into(),
-        width as i32,
-        height as i32,
-        hints.get_dictionary_mut(),
-    ) else {
-        return Err("couldn't encode".to_owned());
-    };
+) -> Result<String, BarcodeError> {
+    let bit_matrix = encode_bit_matrix(data, width, height, bc_type, hints.get_dictionary())?;
     Ok(bit_matrix.to_string())
 }
 
+#[wasm_bindgen]
+/// Encode a barcode with the given data, dimensions, and type, and return its modules as a `BitMatrix`
+pub fn encode_barcode_matrix(
+    data: &str,
+    width: u32,
+    height: u32,
+    bc_type: BarcodeFormat,
+    hints: &mut EncodeHintDictionary,
+) -> Result<BitMatrix, BarcodeError> {
+    encode_bit_matrix(data, width, height, bc_type, hints.get_dictionary()).map(BitMatrix::from)
+}
+
+#[wasm_bindgen]
+/// Encode a barcode with the given data, dimensions, and type, and render it as an SVG document.
+///
+/// Adjacent modules are merged into paths. The quiet zone and colors come from `options`,
+/// colors accept any SVG color value.
+pub fn encode_barcode_svg(
+    data: &str,
+    width: u32,
+    height: u32,
+    bc_type: BarcodeFormat,
+    hints: &mut EncodeHintDictionary,
+    options: &RenderOptions,
+) -> Result<String, BarcodeError> {
+    let bit_matrix = encode_bit_matrix(data, width, height, bc_type, hints.get_dictionary())?;
+    Ok(render::bit_matrix_to_svg(&bit_matrix, options))
+}
+
+#[wasm_bindgen]
+/// Encode a barcode with the given data, dimensions, and type, and render it as raw RGBA pixels.
+///
+/// The quiet zone and colors come from `options`, colors must be hex values such as `#RRGGBB`.
+pub fn encode_barcode_rgba(
+    data: &str,
+    width: u32,
+    height: u32,
+    bc_type: BarcodeFormat,
+    hints: &mut EncodeHintDictionary,
+    options: &RenderOptions,
+) -> Result<RgbaImage, BarcodeError> {
+    let bit_matrix = encode_bit_matrix(data, width, height, bc_type, hints.get_dictionary())?;
+    render::bit_matrix_to_rgba(&bit_matrix, options)
+}
+
+#[wasm_bindgen]
+/// Encode a barcode with the given data, dimensions, and type, and render it as an `ImageData`
+/// object ready to be drawn with `putImageData`.
+pub fn encode_barcode_image_data(
+    data: &str,
+    width: u32,
+    height: u32,
+    bc_type: BarcodeFormat,
+    hints: &mut EncodeHintDictionary,
+    options: &RenderOptions,
+) -> Result<web_sys::ImageData, JsValue> {
+    encode_barcode_rgba(data, width, height, bc_type, hints, options)?.into_image_data()
+}
+
+#[cfg(feature = "png_output")]
+#[wasm_bindgen]
+/// Encode a barcode with the given data, dimensions, and type, and return it as PNG file bytes.
+///
+/// `color_type` selects 1 bit grayscale or RGBA output. Scale, quiet zone, colors and DPI come
+/// from `options`.
+pub fn encode_barcode_png(
+    data: &str,
+    width: u32,
+    height: u32,
+    bc_type: BarcodeFormat,
+    hints: &mut EncodeHintDictionary,
+    options: &RenderOptions,
+    color_type: PngColorType,
+) -> Result<Vec<u8>, BarcodeError> {
+    let bit_matrix = encode_bit_matrix(data, width, height, bc_type, hints.get_dictionary())?;
+    render::bit_matrix_to_png(&bit_matrix, options, color_type)
+}
+
+#[wasm_bindgen]
+/// Encode a barcode and draw it into the 2d context of an `HTMLCanvasElement` or `OffscreenCanvas`.
+///
+/// Every module is drawn with the same whole number of pixels so edges stay crisp. When
+/// `options.scale` is 0 the largest scale that fits the canvas is used and the symbol is centered,
+/// otherwise the canvas is resized to fit the symbol. Linear formats fill the available height.
+pub fn encode_to_canvas(
+    #[wasm_bindgen(unchecked_param_type = "HTMLCanvasElement | OffscreenCanvas")] canvas: &JsValue,
+    data: &str,
+    bc_type: BarcodeFormat,
+    hints: &mut EncodeHintDictionary,
+    options: &RenderOptions,
+) -> Result<(), JsValue> {
+    let (canvas_width, canvas_height) =
+        if let Some(canvas) = canvas.dyn_ref::<web_sys::HtmlCanvasElement>() {
+            (canvas.width(), canvas.height())
+        } else if let Some(canvas) = canvas.dyn_ref::<web_sys::OffscreenCanvas>() {
+            (canvas.width(), canvas.height())
+        } else {
+            return Err(BarcodeError::illegal_argument(
+                "expected an HTMLCanvasElement or OffscreenCanvas",
+            )
+            .into());
+        };
+
+    let mut bit_matrix = encode_bit_matrix(data, 0, 0, bc_type, hints.get_dictionary())?;
+    let scale = if options.scale() > 0 {
+        options.scale()
+    } else if bit_matrix.height() == 1 {
+        options.fit_scale(&bit_matrix, canvas_width, u32::MAX)
+    } else {
+        options.fit_scale(&bit_matrix, canvas_width, canvas_height)
+    };
+    if bit_matrix.height() == 1 {
+        // linear symbols are a single row at their minimum size, stretch them to the canvas
+        let rows = (canvas_height / scale)
+            .saturating_sub(options.quiet_zone().saturating_mul(2))
+            .clamp(1, MAX_ENCODE_DIMENSION);
+        bit_matrix = encode_bit_matrix(
+            data,
+            bit_matrix.width(),
+            rows,
+            bc_type,
+            hints.get_dictionary(),
+        )?;
+    }
+
+    let resize = options.scale() > 0 || canvas_width == 0 || canvas_height == 0;
+    let mut options = options.clone();
+    options.set_scale(scale);
+    let (width, height) = if resize {
+        options.rendered_size(&bit_matrix)?
+    } else {
+        (canvas_width, canvas_height)
+    };
+    let image_data = render::bit_matrix_to_rgba_sized(&bit_matrix, &options, width, height)?
+        .into_image_data()?;
+
+    if let Some(canvas) = canvas.dyn_ref::<web_sys::HtmlCanvasElement>() {
+        if resize {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+        canvas
+            .get_context("2d")?
+            .ok_or_else(|| BarcodeError::illegal_argument("no 2d context available"))?
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()?
+            .put_image_data(&image_data, 0.0, 0.0)
+    } else {
+        let canvas = canvas.unchecked_ref::<web_sys::OffscreenCanvas>();
+        if resize {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+        canvas
+            .get_context("2d")?
+            .ok_or_else(|| BarcodeError::illegal_argument("no 2d context available"))?
+            .dyn_into::<web_sys::OffscreenCanvasRenderingContext2d>()?
+            .put_image_data(&image_data, 0.0, 0.0)
+    }
+}
+
+/// Decode a barcode from luma 8 data, trying each of `binarizers` in turn.
+///
+/// The filtered reader binarizes its own downscaled copies of the image, so `binarizers` is not
+/// used when `filter_image` is set. A decode cut short by `limits` fails with a timeout or abort.
+fn detect_in_luma(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    hints: &mut DecodeHints,
+    limits: &DecodeLimits,
+    filter_image: Option<bool>,
+    binarizers: &Binarizers,
+) -> Result<rxing::RXingResult, BarcodeError> {
+    use rxing::{common::HybridBinarizer, BinaryBitmap, FilteredImageReader};
+
+    hints.TryHarder = hints.TryHarder.or(Some(true));
+    let source = SharedLumaSource::new(data, width, height)?;
+    let deadline = Deadline::start(limits);
+    let result = if matches!(filter_image, Some(true)) {
+        FilteredImageReader::new(DiagnosticReader::with_deadline(&deadline))
+            .decode_with_hints(&mut BinaryBitmap::new(HybridBinarizer::new(source)), hints)
+    } else {
+        let mut reader = DiagnosticReader::new(hints, &deadline);
+        binarizer::decode_with_binarizers(source, binarizers, |image| {
+            reader.decode_with_state(image)
+        })
+    };
+    result.map_err(|error| deadline.error(error))
+}
+
+/// Decode every barcode in luma 8 data, trying each of `binarizers` in turn until one finds any.
+///
+/// As with `detect_in_luma`, `binarizers` is not used when `filter_image` is set. A decode cut
+/// short by `deadline` returns what it found before stopping, if anything. The deadline is not
+/// restarted, so callers that decode a frame more than once can share one budget between them.
+#[cfg(feature = "decode_hints")]
+fn detect_multiple_in_luma(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    hints: &mut DecodeHints,
+    deadline: &Deadline,
+    filter_image: Option<bool>,
+    binarizers: &Binarizers,
+) -> Result<Vec<rxing::RXingResult>, BarcodeError> {
+    use rxing::{
+        common::HybridBinarizer,
+        multi::{GenericMultipleBarcodeReader, MultipleBarcodeReader},
+        BinaryBitmap, FilteredImageReader,
+    };
+
+    let source = SharedLumaSource::new(data, width, height)?;
+    let results = if matches!(filter_image, Some(true)) {
+        GenericMultipleBarcodeReader::new(FilteredImageReader::new(
+            DiagnosticReader::with_deadline(deadline),
+        ))
+        .decode_multiple_with_hints(&mut BinaryBitmap::new(HybridBinarizer::new(source)), hints)
+    } else {
+        hints.TryHarder = hints.TryHarder.or(Some(true));
+        let mut reader =
+            GenericMultipleBarcodeReader::new(DiagnosticReader::with_deadline(deadline));
+        binarizer::decode_with_binarizers(source, binarizers, |image| {
+            reader.decode_multiple_with_hints(image, hints)
+        })
+    };
+    results.map_err(|error| deadline.error(error))
+}
+
 #[wasm_bindgen]
 /// Decode a barcode from an array of 8bit luma data
 pub fn decode_barcode(
@@ -306,21 +642,35 @@ pub fn decode_barcode(
     height: u32,
     try_harder: Option<bool>,
     filter_image: Option<bool>,
-) -> Result<BarcodeResult, String> {
+) -> Result<BarcodeResult, BarcodeError> {
+    decode_barcode_with_binarizers(data, width, height, try_harder, filter_image, Vec::new())
+}
+
+#[wasm_bindgen]
+/// Decode a barcode from an array of 8bit luma data, trying each of `binarizers` in turn until
+/// one of them finds a barcode. An empty list uses `Binarizer.Hybrid`.
+pub fn decode_barcode_with_binarizers(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    try_harder: Option<bool>,
+    filter_image: Option<bool>,
+    binarizers: Vec<Binarizer>,
+) -> Result<BarcodeResult, BarcodeError> {
     let mut hints: DecodeHints = DecodeHints {
         TryHarder: try_harder,
         ..Default::default()
     };
 
-    let detection_function = if matches!(filter_image, Some(true)) {
-        rxing::helpers::detect_in_luma_filtered_with_hints
-    } else {
-        rxing::helpers::detect_in_luma_with_hints
-    };
-
-    let Ok(result) = detection_function(data, width, height, None, &mut hints) else {
-        return Err("not found".to_owned());
-    };
+    let result = detect_in_luma(
+        data,
+        width,
+        height,
+        &mut hints,
+        &DecodeLimits::default(),
+        filter_image,
+        &Binarizers::new(binarizers, DEFAULT_FIXED_THRESHOLD),
+    )?;
     Ok(result.into())
 }
 
@@ -331,7 +681,28 @@ pub fn decode_barcode(
 /// using the `data` property of an `ImageData` object.
 /// Such an object could be obtained using the `getImageData`
 /// method of a `CanvasRenderingContext2D` object.
+///
+/// Any trailing partial pixel is ignored, use `convert_js_image_to_luma_checked`
+/// to have it rejected instead.
 pub fn convert_js_image_to_luma(data: &[u8]) -> Vec<u8> {
+    rgba_to_luma(data)
+}
+
+#[wasm_bindgen]
+/// Convert a javascript image context's data into luma 8, throwing
+/// `InvalidInput` if it isn't a whole number of RGBA pixels.
+pub fn convert_js_image_to_luma_checked(data: &[u8]) -> Result<Vec<u8>, BarcodeError> {
+    if !data.len().is_multiple_of(4) {
+        return Err(BarcodeError::invalid_input(format!(
+            "buffer of {} bytes is not a whole number of RGBA pixels",
+            data.len()
+        )));
+    }
+    Ok(rgba_to_luma(data))
+}
+
+/// Convert tightly packed RGBA pixels into luma 8, any trailing partial pixel is ignored
+pub(crate) fn rgba_to_luma(data: &[u8]) -> Vec<u8> {
     let mut luma_data = Vec::with_capacity(data.len() / 4);
     for src_pixel in data.chunks_exact(4) {
         let [red, green, blue, alpha] = src_pixel else {
@@ -341,12 +712,7 @@ pub fn convert_js_image_to_luma(data: &[u8]) -> Vec<u8> {
             // white, so we know its luminance is 255
             0xFF
         } else {
-            // .299R + 0.587G + 0.114B (YUV/YIQ for PAL and NTSC),
-            // (306*R) >> 10 is approximately equal to R*0.299, and so on.
-            // 0x200 >> 10 is 0.5, it implements rounding.
-
-            ((306 * (*red as u64) + 601 * (*green as u64) + 117 * (*blue as u64) + 0x200) >> 10)
-                as u8
+            pixel_format::rgb_to_luma(*red, *green, *blue)
         };
         luma_data.push(pixel);
     }
@@ -360,7 +726,7 @@ pub fn convert_canvas_to_luma(
 ) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
     let context = canvas
         .get_context("2d")?
-        .ok_or(JsValue::from_str("no context available"))?
+        .ok_or_else(|| BarcodeError::illegal_argument("no 2d context available"))?
         .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
     let image_data =
         context.get_image_data(0.0, 0.0, canvas.width() as f64, canvas.height() as f64)?;
@@ -370,38 +736,69 @@ pub fn convert_canvas_to_luma(
 
 #[wasm_bindgen]
 pub fn convert_imagedata_to_luma(image_data: &web_sys::ImageData) -> Vec<u8> {
-    convert_js_image_to_luma(&image_data.data())
+    rgba_to_luma(&image_data.data())
+}
+
+#[wasm_bindgen]
+/// Convert a raw pixel buffer in the given format into luma 8.
+///
+/// `stride` is the number of bytes from the start of one row to the start of the next.
+/// Leave it unset, or pass 0, when rows are tightly packed.
+pub fn convert_raw_to_luma(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: Option<u32>,
+    pixel_format: PixelFormat,
+) -> Result<Vec<u8>, BarcodeError> {
+    pixel_format::raw_to_luma(data, width, height, stride.unwrap_or(0), pixel_format)
 }
 
 #[wasm_bindgen]
-/// Decode a barcode from an array of rgba data.
+/// Decode a barcode from an array of rgb data.
 /// Pixel data is in the form of:
-///     Each pixel is one u32, [r,g,b].
+///     Each pixel is one u32, packed as 0x00RRGGBB. The high byte is ignored.
 pub fn decode_barcode_rgb(
     data: Vec<u32>,
     width: u32,
     height: u32,
     try_harder: Option<bool>,
-) -> Result<BarcodeResult, String> {
-    let hints: DecodeHints = DecodeHints {
-        TryHarder: try_harder,
+) -> Result<BarcodeResult, BarcodeError> {
+    decode_barcode_rgb_with_binarizers(data, width, height, try_harder, Vec::new())
+}
+
+#[wasm_bindgen]
+/// Decode a barcode from an array of rgb data packed as in `decode_barcode_rgb`, trying each of
+/// `binarizers` in turn until one of them finds a barcode. An empty list uses `Binarizer.Hybrid`.
+pub fn decode_barcode_rgb_with_binarizers(
+    data: Vec<u32>,
+    width: u32,
+    height: u32,
+    try_harder: Option<bool>,
+    binarizers: Vec<Binarizer>,
+) -> Result<BarcodeResult, BarcodeError> {
+    let mut hints: DecodeHints = DecodeHints {
+        // unlike the luma functions, this one doesn't try harder unless asked to
+        TryHarder: Some(try_harder.unwrap_or(false)),
         ..Default::default()
     };
 
-    let mut multi_format_reader = rxing::MultiFormatReader::default();
-
-    let Ok(result) = multi_format_reader.decode_with_hints(
-        &mut rxing::BinaryBitmap::new(rxing::common::HybridBinarizer::new(
-            rxing::RGBLuminanceSource::new_with_width_height_pixels(
-                width as usize,
-                height as usize,
-                &data,
-            ),
-        )),
-        &hints,
-    ) else {
-        return Err("not found".to_owned());
-    };
+    // rxing's RGB source can't be rotated or read by column, which some readers need
+    let luma = data
+        .iter()
+        .map(|pixel| {
+            pixel_format::rgb_to_luma((pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8)
+        })
+        .collect();
+    let result = detect_in_luma(
+        luma,
+        width,
+        height,
+        &mut hints,
+        &DecodeLimits::default(),
+        None,
+        &Binarizers::new(binarizers, DEFAULT_FIXED_THRESHOLD),
+    )?;
 
     Ok(result.into())
 }
@@ -414,31 +811,109 @@ pub fn decode_barcode_with_hints(
     height: u32,
     hints: &mut decode_hints::DecodeHintDictionary,
     filter_image: Option<bool>,
-) -> Result<BarcodeResult, String> {
-    let results = if matches!(filter_image, Some(true)) {
-        rxing::helpers::detect_in_luma_filtered_with_hints(
-            data,
-            width,
-            height,
-            None,
-            hints.get_dictionary_mut(),
-        )
-    } else {
-        rxing::helpers::detect_in_luma_with_hints(
-            data,
-            width,
-            height,
-            None,
-            hints.get_dictionary_mut(),
-        )
-    };
+) -> Result<BarcodeResult, BarcodeError> {
+    decode_barcode_with_hints_and_binarizers(data, width, height, hints, filter_image, Vec::new())
+}
 
-    let Ok(result) = results else {
-        return Err("not found".to_owned());
-    };
+#[cfg(feature = "decode_hints")]
+#[wasm_bindgen]
+/// Decode a barcode from an array of 8bit luma data with `hints`, trying each of `binarizers` in
+/// turn until one of them finds a barcode. An empty list uses `Binarizer.Hybrid`.
+pub fn decode_barcode_with_hints_and_binarizers(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    hints: &mut decode_hints::DecodeHintDictionary,
+    filter_image: Option<bool>,
+    binarizers: Vec<Binarizer>,
+) -> Result<BarcodeResult, BarcodeError> {
+    let limits = hints.limits().clone();
+    let binarizers = hints.binarizers(binarizers);
+    let result = detect_in_luma(
+        data,
+        width,
+        height,
+        hints.get_dictionary_mut(),
+        &limits,
+        filter_image,
+        &binarizers,
+    )?;
     Ok(result.into())
 }
 
+#[cfg(feature = "image_decode")]
+#[wasm_bindgen]
+/// Decode a barcode from the bytes of an encoded image file.
+///
+/// PNG, JPEG, GIF, WebP and BMP files are supported, the format is detected from the contents.
+/// EXIF orientation is applied before detection. Requires the `image_decode` feature.
+pub fn decode_image_bytes(
+    bytes: &[u8],
+    hints: &mut decode_hints::DecodeHintDictionary,
+    filter_image: Option<bool>,
+    binarizers: Option<Vec<Binarizer>>,
+) -> Result<BarcodeResult, BarcodeError> {
+    let (luma, width, height) = image_bytes::image_bytes_to_luma(bytes)?;
+    decode_barcode_with_hints_and_binarizers(
+        luma,
+        width,
+        height,
+        hints,
+        filter_image,
+        binarizers.unwrap_or_default(),
+    )
+}
+
+#[cfg(feature = "decode_hints")]
+#[wasm_bindgen]
+/// Decode a barcode from a raw pixel buffer in any supported `PixelFormat`.
+///
+/// `stride` is the number of bytes from the start of one row to the start of the next, which
+/// allows padded rows. Leave it unset, or pass 0, when rows are tightly packed.
+pub fn decode_raw(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: Option<u32>,
+    pixel_format: PixelFormat,
+    hints: &mut decode_hints::DecodeHintDictionary,
+    binarizers: Option<Vec<Binarizer>>,
+) -> Result<BarcodeResult, BarcodeError> {
+    let luma = convert_raw_to_luma(data, width, height, stride, pixel_format)?;
+    decode_barcode_with_hints_and_binarizers(
+        luma,
+        width,
+        height,
+        hints,
+        None,
+        binarizers.unwrap_or_default(),
+    )
+}
+
+#[cfg(feature = "decode_hints")]
+#[wasm_bindgen]
+/// Decode a barcode from a planar or semi-planar YUV frame (I420, NV12, NV21).
+///
+/// Only the visible part of the Y plane described by `layout` is read, so no RGBA conversion is
+/// needed. Result points are relative to the top-left of the visible rectangle.
+pub fn decode_yuv(
+    data: &[u8],
+    layout: &YuvLayout,
+    hints: &mut decode_hints::DecodeHintDictionary,
+    binarizers: Option<Vec<Binarizer>>,
+) -> Result<BarcodeResult, BarcodeError> {
+    let luma = layout.extract_luma(data)?;
+    let (width, height) = layout.visible_size();
+    decode_barcode_with_hints_and_binarizers(
+        luma,
+        width,
+        height,
+        hints,
+        None,
+        binarizers.unwrap_or_default(),
+    )
+}
+
 #[cfg(feature = "decode_hints")]
 #[wasm_bindgen]
 pub fn decode_multi(
@@ -447,36 +922,97 @@ pub fn decode_multi(
     height: u32,
     hints: &mut decode_hints::DecodeHintDictionary,
     filter_image: Option<bool>,
-) -> Result<Vec<BarcodeResult>, String> {
-    use rxing::{
-        common::HybridBinarizer,
-        multi::{GenericMultipleBarcodeReader, MultipleBarcodeReader},
-        BinaryBitmap, FilteredImageReader, Luma8LuminanceSource, MultiFormatReader,
-    };
+) -> Result<Vec<BarcodeResult>, BarcodeError> {
+    decode_multi_with_binarizers(data, width, height, hints, filter_image, Vec::new())
+}
 
-    let result = if matches!(filter_image, Some(true)) {
-        let mut reader = GenericMultipleBarcodeReader::new(FilteredImageReader::new(
-            MultiFormatReader::default(),
-        ));
-
-        reader.decode_multiple_with_hints(
-            &mut BinaryBitmap::new(HybridBinarizer::new(Luma8LuminanceSource::new(
-                data, width, height,
-            ))),
-            hints.get_dictionary_mut(),
-        )
-    } else {
-        rxing::helpers::detect_multiple_in_luma_with_hints(
-            data,
-            width,
-            height,
-            hints.get_dictionary_mut(),
-        )
-    };
-    result
-        .map(|value| {
-            let r: Vec<BarcodeResult> = value.into_iter().map(|v| v.into()).collect();
-            r
-        })
-        .map_err(|err| err.to_string())
+#[cfg(feature = "decode_hints")]
+#[wasm_bindgen]
+/// Decode all barcodes in an array of 8bit luma data with `hints`, trying each of `binarizers` in
+/// turn until one of them finds any. An empty list uses `Binarizer.Hybrid`.
+pub fn decode_multi_with_binarizers(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    hints: &mut decode_hints::DecodeHintDictionary,
+    filter_image: Option<bool>,
+    binarizers: Vec<Binarizer>,
+) -> Result<Vec<BarcodeResult>, BarcodeError> {
+    let deadline = Deadline::start(hints.limits());
+    let binarizers = hints.binarizers(binarizers);
+    let result = detect_multiple_in_luma(
+        data,
+        width,
+        height,
+        hints.get_dictionary_mut(),
+        &deadline,
+        filter_image,
+        &binarizers,
+    );
+    result.map(|value| {
+        let r: Vec<BarcodeResult> = value.into_iter().map(|v| v.into()).collect();
+        r
+    })
+}
+
+#[cfg(feature = "decode_hints")]
+#[wasm_bindgen]
+/// Decode a barcode from an array of 8bit luma data, searching only inside `region`.
+///
+/// Only the region is binarized and searched. Result points are mapped back into the
+/// coordinates of the full image.
+pub fn decode_barcode_in_region(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    region: &Region,
+    hints: &mut decode_hints::DecodeHintDictionary,
+    filter_image: Option<bool>,
+    binarizers: Option<Vec<Binarizer>>,
+) -> Result<BarcodeResult, BarcodeError> {
+    let cropped = region.crop_luma(&data, width, height)?;
+    // candidate points are reported in the coordinates of the full image, like the results
+    let mut hints = hints.translated(region.x as f32, region.y as f32);
+    let mut result = decode_barcode_with_hints_and_binarizers(
+        cropped,
+        region.width,
+        region.height,
+        &mut hints,
+        filter_image,
+        binarizers.unwrap_or_default(),
+    )?;
+    result.translate(region.x as f32, region.y as f32);
+    Ok(result)
+}
+
+#[cfg(feature = "decode_hints")]
+#[wasm_bindgen]
+/// Decode all barcodes in an array of 8bit luma data, searching only inside `region`.
+///
+/// Only the region is binarized and searched. Result points are mapped back into the
+/// coordinates of the full image.
+pub fn decode_multi_in_region(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    region: &Region,
+    hints: &mut decode_hints::DecodeHintDictionary,
+    filter_image: Option<bool>,
+    binarizers: Option<Vec<Binarizer>>,
+) -> Result<Vec<BarcodeResult>, BarcodeError> {
+    let cropped = region.crop_luma(&data, width, height)?;
+    // candidate points are reported in the coordinates of the full image, like the results
+    let mut hints = hints.translated(region.x as f32, region.y as f32);
+    let mut results = decode_multi_with_binarizers(
+        cropped,
+        region.width,
+        region.height,
+        &mut hints,
+        filter_image,
+        binarizers.unwrap_or_default(),
+    )?;
+    for result in &mut results {
+        result.translate(region.x as f32, region.y as f32);
+    }
+    Ok(results)
 }
@@ -0,0 +1,167 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+
+use rxing::LuminanceSource;
+
+use crate::error::BarcodeError;
+
+/// Check that a buffer of `len` pixels holds exactly one non-empty `width` x `height` image
+pub(crate) fn check_pixel_count(len: usize, width: u32, height: u32) -> Result<(), BarcodeError> {
+    if width == 0 || height == 0 {
+        return Err(BarcodeError::invalid_input(format!(
+            "image of {width}x{height} pixels is empty"
+        )));
+    }
+    let expected = width as u64 * height as u64;
+    if len as u64 != expected {
+        return Err(BarcodeError::invalid_input(format!(
+            "buffer of {len} pixels does not match a {width}x{height} image ({expected} pixels)"
+        )));
+    }
+    Ok(())
+}
+
+/// A luma 8 source over a shared buffer.
+///
+/// Clones share the buffer, which makes it cheap to binarize the same image several times, and
+/// the buffer can be taken back once decoding is done. Crops and rotations copy into a new one.
+#[derive(Clone)]
+pub(crate) struct SharedLumaSource {
+    data: Rc<Vec<u8>>,
+    width: usize,
+    height: usize,
+    inverted: bool,
+    rotatable: bool,
+}
+
+impl SharedLumaSource {
+    /// Wrap tightly packed luma 8 pixels, which must fill exactly `width` x `height`
+    pub(crate) fn new(data: Vec<u8>, width: u32, height: u32) -> Result<Self, BarcodeError> {
+        check_pixel_count(data.len(), width, height)?;
+        Ok(Self {
+            data: Rc::new(data),
+            width: width as usize,
+            height: height as usize,
+            inverted: false,
+            rotatable: true,
+        })
+    }
+
+    /// Stop readers from rotating this source themselves, so the upright and rotated image can
+    /// be searched in separate passes
+    #[cfg(feature = "decode_hints")]
+    pub(crate) fn upright(mut self) -> Self {
+        self.rotatable = false;
+        self
+    }
+
+    /// The original buffer, if no clone of this source is still alive
+    #[cfg(feature = "decode_hints")]
+    pub(crate) fn into_buffer(self) -> Option<Vec<u8>> {
+        Rc::try_unwrap(self.data).ok()
+    }
+
+    fn pixel(&self, value: u8) -> u8 {
+        if self.inverted {
+            255 - value
+        } else {
+            value
+        }
+    }
+}
+
+impl LuminanceSource for SharedLumaSource {
+    const SUPPORTS_CROP: bool = true;
+    const SUPPORTS_ROTATION: bool = true;
+
+    fn get_row(&self, y: usize) -> Option<Cow<'_, [u8]>> {
+        let row = self.data.get(y * self.width..(y + 1) * self.width)?;
+        if self.inverted {
+            Some(Cow::Owned(row.iter().map(|&value| 255 - value).collect()))
+        } else {
+            Some(Cow::Borrowed(row))
+        }
+    }
+
+    fn get_column(&self, x: usize) -> Vec<u8> {
+        (0..self.height)
+            .map(|y| self.pixel(self.data[y * self.width + x]))
+            .collect()
+    }
+
+    fn get_matrix(&self) -> Cow<'_, [u8]> {
+        let matrix = &self.data[..self.width * self.height];
+        if self.inverted {
+            Cow::Owned(matrix.iter().map(|&value| 255 - value).collect())
+        } else {
+            Cow::Borrowed(matrix)
+        }
+    }
+
+    fn get_width(&self) -> usize {
+        self.width
+    }
+
+    fn get_height(&self) -> usize {
+        self.height
+    }
+
+    fn is_rotate_supported(&self) -> bool {
+        self.rotatable
+    }
+
+    fn invert(&mut self) {
+        self.inverted = !self.inverted;
+    }
+
+    fn get_luma8_point(&self, x: usize, y: usize) -> u8 {
+        self.pixel(self.data[y * self.width + x])
+    }
+
+    fn crop(
+        &self,
+        left: usize,
+        top: usize,
+        width: usize,
+        height: usize,
+    ) -> rxing::common::Result<Self> {
+        if width == 0 || height == 0 {
+            return Err(rxing::Exceptions::illegal_argument_with(
+                "crop rectangle is empty",
+            ));
+        }
+        if left + width > self.width || top + height > self.height {
+            return Err(rxing::Exceptions::illegal_argument_with(
+                "crop rectangle does not fit in the image",
+            ));
+        }
+        let mut data = Vec::with_capacity(width * height);
+        for row in self.data.chunks_exact(self.width).skip(top).take(height) {
+            data.extend_from_slice(&row[left..left + width]);
+        }
+        Ok(Self {
+            data: Rc::new(data),
+            width,
+            height,
+            inverted: self.inverted,
+            rotatable: self.rotatable,
+        })
+    }
+
+    fn rotate_counter_clockwise(&self) -> rxing::common::Result<Self> {
+        // the rotated image is `height` wide, source column x becomes row (width - 1 - x)
+        let mut data = vec![0; self.width * self.height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                data[(self.width - 1 - x) * self.height + y] = self.data[y * self.width + x];
+            }
+        }
+        Ok(Self {
+            data: Rc::new(data),
+            width: self.height,
+            height: self.width,
+            inverted: self.inverted,
+            rotatable: self.rotatable,
+        })
+    }
+}
@@ -0,0 +1,332 @@
+use rxing::client::result::{
+    parseRXingResult, AddressBookParsedRXingResult, CalendarParsedRXingResult,
+    EmailAddressParsedRXingResult, ExpandedProductParsedRXingResult, GeoParsedRXingResult,
+    ParsedClientResult, SMSParsedRXingResult, VINParsedRXingResult, WifiParsedRXingResult,
+};
+use rxing::RXingResult;
+use wasm_bindgen::prelude::*;
+
+use crate::result_metadata::set;
+use crate::BarcodeResult;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_PARSED_RESULT: &'static str = r#"
+/** The content of a result, interpreted by the first parser that recognises it. Optional fields are only set when present */
+export type ParsedResult =
+    | { type: "URI"; uri: string; title?: string }
+    | {
+          type: "WiFi";
+          ssid: string;
+          password?: string;
+          auth?: string;
+          hidden: boolean;
+          identity?: string;
+          anonymousIdentity?: string;
+          eapMethod?: string;
+          phase2Method?: string;
+      }
+    | {
+          type: "AddressBook";
+          names: string[];
+          nicknames: string[];
+          pronunciation?: string;
+          phoneNumbers: string[];
+          phoneTypes: string[];
+          emails: string[];
+          emailTypes: string[];
+          instantMessenger?: string;
+          note?: string;
+          addresses: string[];
+          addressTypes: string[];
+          title?: string;
+          org?: string;
+          urls: string[];
+          birthday?: string;
+          geo: string[];
+      }
+    | { type: "EmailAddress"; tos: string[]; ccs: string[]; bccs: string[]; subject?: string; body?: string }
+    | { type: "SMS"; uri: string; numbers: string[]; vias: string[]; subject?: string; body?: string }
+    | { type: "Tel"; number: string; uri: string; title?: string }
+    | { type: "Geo"; uri: string; latitude: number; longitude: number; altitude: number; query?: string }
+    | {
+          type: "Calendar";
+          summary?: string;
+          start: Date;
+          startAllDay: boolean;
+          end?: Date;
+          endAllDay: boolean;
+          location?: string;
+          organizer?: string;
+          attendees: string[];
+          description?: string;
+          latitude?: number;
+          longitude?: number;
+      }
+    | { type: "ISBN"; isbn: string }
+    | { type: "Product"; productId: string; normalizedProductId: string }
+    | {
+          type: "ExpandedProduct";
+          rawText: string;
+          productId?: string;
+          sscc?: string;
+          lotNumber?: string;
+          productionDate?: string;
+          packagingDate?: string;
+          bestBeforeDate?: string;
+          expirationDate?: string;
+          weight?: string;
+          weightType?: string;
+          weightIncrement?: string;
+          price?: string;
+          priceIncrement?: string;
+          priceCurrency?: string;
+          uncommonAIs: Record<string, string>;
+      }
+    | {
+          type: "VIN";
+          vin: string;
+          worldManufacturerId: string;
+          vehicleDescriptorSection: string;
+          vehicleIdentifierSection: string;
+          countryCode?: string;
+          vehicleAttributes: string;
+          modelYear: number;
+          plantCode: string;
+          sequentialNumber: string;
+      }
+    | { type: "Text"; text: string; language?: string };
+"#;
+
+#[wasm_bindgen]
+impl BarcodeResult {
+    #[wasm_bindgen(unchecked_return_type = "ParsedResult")]
+    /// The content interpreted as a URI, WiFi network, contact, email, SMS, phone number,
+    /// location, calendar event, ISBN, product code, GS1 DataBar Expanded product, VIN or, when
+    /// nothing else matches, plain text. The `type` field tells them apart.
+    pub fn parsed(&self) -> JsValue {
+        let result = RXingResult::new(
+            &self.text,
+            self.raw_bytes.clone(),
+            Vec::new(),
+            self.format.into(),
+        );
+        let object = js_sys::Object::new();
+        match parseRXingResult(&result) {
+            ParsedClientResult::URIResult(uri) => {
+                set(&object, "type", "URI".into());
+                set(&object, "uri", uri.getURI().into());
+                set_text(&object, "title", uri.getTitle());
+            }
+            ParsedClientResult::WiFiResult(wifi) => set_wifi(&object, &wifi),
+            ParsedClientResult::AddressBookResult(address_book) => {
+                set_address_book(&object, &address_book)
+            }
+            ParsedClientResult::EmailResult(email) => set_email(&object, &email),
+            ParsedClientResult::SMSResult(sms) => set_sms(&object, &sms),
+            ParsedClientResult::TelResult(tel) => {
+                set(&object, "type", "Tel".into());
+                set(&object, "number", tel.getNumber().into());
+                set(&object, "uri", tel.getTelURI().into());
+                set_text(&object, "title", tel.getTitle());
+            }
+            ParsedClientResult::GeoResult(geo) => set_geo(&object, &geo),
+            ParsedClientResult::CalendarEventResult(calendar) => set_calendar(&object, &calendar),
+            ParsedClientResult::ISBNResult(isbn) => {
+                set(&object, "type", "ISBN".into());
+                set(&object, "isbn", isbn.getISBN().into());
+            }
+            ParsedClientResult::ProductResult(product) => {
+                set(&object, "type", "Product".into());
+                set(&object, "productId", product.getProductID().into());
+                set(
+                    &object,
+                    "normalizedProductId",
+                    product.getNormalizedProductID().into(),
+                );
+            }
+            ParsedClientResult::ExpandedProductResult(product) => {
+                set_expanded_product(&object, &product)
+            }
+            ParsedClientResult::VINResult(vin) => set_vin(&object, &vin),
+            ParsedClientResult::TextResult(text) => {
+                set(&object, "type", "Text".into());
+                set(&object, "text", text.getText().into());
+                set_text(&object, "language", text.getLanguage());
+            }
+            // none of rxing's parsers produce these
+            ParsedClientResult::Other(_) => {
+                set(&object, "type", "Text".into());
+                set(&object, "text", self.text.as_str().into());
+            }
+        }
+        object.into()
+    }
+}
+
+fn set_wifi(object: &js_sys::Object, wifi: &WifiParsedRXingResult) {
+    set(object, "type", "WiFi".into());
+    set(object, "ssid", wifi.getSsid().into());
+    set_text(object, "password", wifi.getPassword());
+    set_text(object, "auth", wifi.getNetworkEncryption());
+    set(object, "hidden", wifi.isHidden().into());
+    set_text(object, "identity", wifi.getIdentity());
+    set_text(object, "anonymousIdentity", wifi.getAnonymousIdentity());
+    set_text(object, "eapMethod", wifi.getEapMethod());
+    set_text(object, "phase2Method", wifi.getPhase2Method());
+}
+
+fn set_address_book(object: &js_sys::Object, address_book: &AddressBookParsedRXingResult) {
+    set(object, "type", "AddressBook".into());
+    set(object, "names", strings(address_book.getNames()));
+    set(object, "nicknames", strings(address_book.getNicknames()));
+    set_text(object, "pronunciation", address_book.getPronunciation());
+    set(
+        object,
+        "phoneNumbers",
+        strings(address_book.getPhoneNumbers()),
+    );
+    set(object, "phoneTypes", strings(address_book.getPhoneTypes()));
+    set(object, "emails", strings(address_book.getEmails()));
+    set(object, "emailTypes", strings(address_book.getEmailTypes()));
+    set_text(
+        object,
+        "instantMessenger",
+        address_book.getInstantMessenger(),
+    );
+    set_text(object, "note", address_book.getNote());
+    set(object, "addresses", strings(address_book.getAddresses()));
+    set(
+        object,
+        "addressTypes",
+        strings(address_book.getAddressTypes()),
+    );
+    set_text(object, "title", address_book.getTitle());
+    set_text(object, "org", address_book.getOrg());
+    set(object, "urls", strings(address_book.getURLs()));
+    set_text(object, "birthday", address_book.getBirthday());
+    set(object, "geo", strings(address_book.getGeo()));
+}
+
+fn set_email(object: &js_sys::Object, email: &EmailAddressParsedRXingResult) {
+    set(object, "type", "EmailAddress".into());
+    set(object, "tos", strings(email.getTos()));
+    set(object, "ccs", strings(email.getCCs()));
+    set(object, "bccs", strings(email.getBCCs()));
+    set_text(object, "subject", email.getSubject());
+    set_text(object, "body", email.getBody());
+}
+
+fn set_sms(object: &js_sys::Object, sms: &SMSParsedRXingResult) {
+    set(object, "type", "SMS".into());
+    set(object, "uri", sms.getSMSURI().into());
+    set(object, "numbers", strings(sms.getNumbers()));
+    set(object, "vias", strings(sms.getVias()));
+    set_text(object, "subject", sms.getSubject());
+    set_text(object, "body", sms.getBody());
+}
+
+fn set_geo(object: &js_sys::Object, geo: &GeoParsedRXingResult) {
+    set(object, "type", "Geo".into());
+    set(object, "uri", geo.getGeoURI().into());
+    set(object, "latitude", geo.getLatitude().into());
+    set(object, "longitude", geo.getLongitude().into());
+    set(object, "altitude", geo.getAltitude().into());
+    set_text(object, "query", geo.getQuery());
+}
+
+fn set_calendar(object: &js_sys::Object, calendar: &CalendarParsedRXingResult) {
+    set(object, "type", "Calendar".into());
+    set_text(object, "summary", calendar.getSummary());
+    set(object, "start", date(calendar.getStartTimestamp()));
+    set(object, "startAllDay", calendar.isStartAllDay().into());
+    // rxing reports an event without an end or a duration as ending at -1
+    if calendar.getEndTimestamp() >= 0 {
+        set(object, "end", date(calendar.getEndTimestamp()));
+    }
+    set(object, "endAllDay", calendar.isEndAllDay().into());
+    set_text(object, "location", calendar.getLocation());
+    set_text(object, "organizer", calendar.getOrganizer());
+    set(object, "attendees", strings(calendar.getAttendees()));
+    set_text(object, "description", calendar.getDescription());
+    // and a missing or unreadable GEO property as NaN
+    if !calendar.getLatitude().is_nan() && !calendar.getLongitude().is_nan() {
+        set(object, "latitude", calendar.getLatitude().into());
+        set(object, "longitude", calendar.getLongitude().into());
+    }
+}
+
+fn set_expanded_product(object: &js_sys::Object, product: &ExpandedProductParsedRXingResult) {
+    set(object, "type", "ExpandedProduct".into());
+    set(object, "rawText", product.getRawText().into());
+    for (name, value) in [
+        ("productId", product.getProductID()),
+        ("sscc", product.getSscc()),
+        ("lotNumber", product.getLotNumber()),
+        ("productionDate", product.getProductionDate()),
+        ("packagingDate", product.getPackagingDate()),
+        ("bestBeforeDate", product.getBestBeforeDate()),
+        ("expirationDate", product.getExpirationDate()),
+        ("weight", product.getWeight()),
+        ("weightType", product.getWeightType()),
+        ("weightIncrement", product.getWeightIncrement()),
+        ("price", product.getPrice()),
+        ("priceIncrement", product.getPriceIncrement()),
+        ("priceCurrency", product.getPriceCurrency()),
+    ] {
+        set_text(object, name, value);
+    }
+    let uncommon_ais = js_sys::Object::new();
+    for (ai, value) in product.getUncommonAIs() {
+        set(&uncommon_ais, ai, value.as_str().into());
+    }
+    set(object, "uncommonAIs", uncommon_ais.into());
+}
+
+fn set_vin(object: &js_sys::Object, vin: &VINParsedRXingResult) {
+    set(object, "type", "VIN".into());
+    set(object, "vin", vin.getVIN().into());
+    set(
+        object,
+        "worldManufacturerId",
+        vin.getWorldManufacturerID().into(),
+    );
+    set(
+        object,
+        "vehicleDescriptorSection",
+        vin.getVehicleDescriptorSection().into(),
+    );
+    set(
+        object,
+        "vehicleIdentifierSection",
+        vin.getVehicleIdentifierSection().into(),
+    );
+    set_text(object, "countryCode", vin.getCountryCode());
+    set(
+        object,
+        "vehicleAttributes",
+        vin.getVehicleAttributes().into(),
+    );
+    set(object, "modelYear", vin.getModelYear().into());
+    set(object, "plantCode", vin.getPlantCode().to_string().into());
+    set(object, "sequentialNumber", vin.getSequentialNumber().into());
+}
+
+/// Set `key` to `value` unless it is empty, which is how rxing marks fields that weren't present
+fn set_text(object: &js_sys::Object, key: &str, value: &str) {
+    if !value.is_empty() {
+        set(object, key, value.into());
+    }
+}
+
+fn strings(values: &[String]) -> JsValue {
+    values
+        .iter()
+        .map(|value| JsValue::from_str(value))
+        .collect::<js_sys::Array>()
+        .into()
+}
+
+/// A JS `Date` from rxing's timestamps, which are in seconds
+fn date(seconds: i64) -> JsValue {
+    js_sys::Date::new(&JsValue::from_f64(seconds as f64 * 1000.0)).into()
+}
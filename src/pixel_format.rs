@@ -0,0 +1,232 @@
+use crate::error::BarcodeError;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+/// Memory layout of raw pixel buffers
+pub enum PixelFormat {
+    /** Four bytes per pixel: red, green, blue, alpha. The layout of canvas `ImageData`. */
+    Rgba8,
+
+    /** Four bytes per pixel: blue, green, red, alpha. Common for camera SDKs and native addons. */
+    Bgra8,
+
+    /** Three bytes per pixel: red, green, blue. */
+    Rgb8,
+
+    /** One little-endian 32 bit integer per pixel, laid out as 0xAARRGGBB. */
+    Argb32,
+
+    /**
+     * One little-endian 32 bit integer per pixel, laid out as 0x00RRGGBB. This is the packing
+     * `decode_barcode_rgb` expects, and the high byte is ignored in the same way.
+     */
+    Xrgb32,
+
+    /** One little-endian 16 bit integer per pixel, 5 bits red, 6 bits green, 5 bits blue. */
+    Rgb565,
+
+    /** One byte per pixel of luma. */
+    Gray8,
+
+    /** One little-endian 16 bit integer per pixel of luma. */
+    Gray16,
+
+    /** Two bytes per pixel: luma, alpha. */
+    GrayAlpha,
+}
+
+impl PixelFormat {
+    pub(crate) const fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 | PixelFormat::Argb32 | PixelFormat::Xrgb32 => {
+                4
+            }
+            PixelFormat::Rgb8 => 3,
+            PixelFormat::Rgb565 | PixelFormat::Gray16 | PixelFormat::GrayAlpha => 2,
+            PixelFormat::Gray8 => 1,
+        }
+    }
+
+    fn pixel_to_luma(&self, pixel: &[u8]) -> u8 {
+        match *self {
+            PixelFormat::Rgba8 => {
+                with_alpha(pixel[3], || rgb_to_luma(pixel[0], pixel[1], pixel[2]))
+            }
+            PixelFormat::Bgra8 => {
+                with_alpha(pixel[3], || rgb_to_luma(pixel[2], pixel[1], pixel[0]))
+            }
+            PixelFormat::Argb32 => {
+                with_alpha(pixel[3], || rgb_to_luma(pixel[2], pixel[1], pixel[0]))
+            }
+            PixelFormat::Xrgb32 => rgb_to_luma(pixel[2], pixel[1], pixel[0]),
+            PixelFormat::Rgb8 => rgb_to_luma(pixel[0], pixel[1], pixel[2]),
+            PixelFormat::Rgb565 => {
+                let value = u16::from_le_bytes([pixel[0], pixel[1]]);
+                // expand each channel to 8 bits by repeating its high bits
+                let red = ((value >> 11) & 0x1F) as u8;
+                let green = ((value >> 5) & 0x3F) as u8;
+                let blue = (value & 0x1F) as u8;
+                rgb_to_luma(
+                    (red << 3) | (red >> 2),
+                    (green << 2) | (green >> 4),
+                    (blue << 3) | (blue >> 2),
+                )
+            }
+            PixelFormat::Gray8 => pixel[0],
+            PixelFormat::Gray16 => pixel[1],
+            PixelFormat::GrayAlpha => with_alpha(pixel[1], || pixel[0]),
+        }
+    }
+}
+
+/// Fully transparent pixels are treated as white, matching `convert_js_image_to_luma`
+fn with_alpha(alpha: u8, luma: impl FnOnce() -> u8) -> u8 {
+    if alpha == 0 {
+        0xFF
+    } else {
+        luma()
+    }
+}
+
+pub(crate) fn rgb_to_luma(red: u8, green: u8, blue: u8) -> u8 {
+    // .299R + 0.587G + 0.114B (YUV/YIQ for PAL and NTSC),
+    // (306*R) >> 10 is approximately equal to R*0.299, and so on.
+    // 0x200 >> 10 is 0.5, it implements rounding.
+    ((306 * (red as u64) + 601 * (green as u64) + 117 * (blue as u64) + 0x200) >> 10) as u8
+}
+
+/// Convert a raw pixel buffer into tightly packed luma 8.
+///
+/// `stride` is the number of bytes from the start of one row to the start of the next,
+/// 0 means rows are tightly packed.
+pub(crate) fn raw_to_luma(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    pixel_format: PixelFormat,
+) -> Result<Vec<u8>, BarcodeError> {
+    let mut luma = Vec::new();
+    raw_to_luma_into(data, width, height, stride, pixel_format, &mut luma)?;
+    Ok(luma)
+}
+
+/// Same as `raw_to_luma`, but writes into `luma`, replacing its contents and reusing its allocation
+pub(crate) fn raw_to_luma_into(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    pixel_format: PixelFormat,
+    luma: &mut Vec<u8>,
+) -> Result<(), BarcodeError> {
+    if width == 0 || height == 0 {
+        return Err(BarcodeError::invalid_input(format!(
+            "image of {width}x{height} pixels is empty"
+        )));
+    }
+    // sizes are worked out in 64 bits so they can't overflow a 32 bit usize
+    let bytes_per_pixel = pixel_format.bytes_per_pixel();
+    let row_length = width as u64 * bytes_per_pixel as u64;
+    let stride = if stride == 0 {
+        row_length
+    } else {
+        stride as u64
+    };
+    if stride < row_length {
+        return Err(BarcodeError::invalid_input(format!(
+            "stride of {stride} bytes is shorter than a row of {width} {pixel_format:?} pixels ({row_length} bytes)"
+        )));
+    }
+    let required = stride * (height as u64 - 1) + row_length;
+    if (data.len() as u64) < required {
+        return Err(BarcodeError::invalid_input(format!(
+            "buffer of {} bytes is too small for {width}x{height} {pixel_format:?} pixels with a stride of {stride} bytes ({required} bytes)",
+            data.len()
+        )));
+    }
+    // both fit in the buffer, so they fit in a usize
+    let (row_length, stride) = (row_length as usize, stride as usize);
+
+    luma.clear();
+    luma.reserve(width as usize * height as usize);
+    for row in data.chunks(stride).take(height as usize) {
+        luma.extend(
+            row[..row_length]
+                .chunks_exact(bytes_per_pixel)
+                .map(|pixel| pixel_format.pixel_to_luma(pixel)),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A QR code as 0x00RRGGBB pixels, with a high byte of 0 as `decode_barcode_rgb` allows
+    #[cfg(feature = "decode_hints")]
+    fn rgb_qr_code(text: &str) -> (Vec<u32>, u32, u32) {
+        use rxing::{BarcodeFormat, Writer};
+
+        let matrix = rxing::qrcode::QRCodeWriter
+            .encode(text, &BarcodeFormat::QR_CODE, 120, 120)
+            .unwrap();
+        let (width, height) = (matrix.width(), matrix.height());
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                if matrix.get(x, y) {
+                    0x00000000
+                } else {
+                    0x00FFFFFF
+                }
+            })
+            .collect();
+        (pixels, width, height)
+    }
+
+    fn packed(pixels: &[u32]) -> Vec<u8> {
+        pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn argb32_treats_transparent_pixels_as_white() {
+        let bytes = packed(&[0xFFFF_FFFF, 0xFF00_0000, 0x0000_0000, 0x8080_8080]);
+        let luma = raw_to_luma(&bytes, 4, 1, 0, PixelFormat::Argb32).unwrap();
+        assert_eq!(luma, [255, 0, 255, 128]);
+    }
+
+    #[test]
+    fn xrgb32_ignores_the_high_byte() {
+        let bytes = packed(&[0x00FF_FFFF, 0x0000_0000, 0xFF00_0000, 0x0080_8080]);
+        let luma = raw_to_luma(&bytes, 4, 1, 0, PixelFormat::Xrgb32).unwrap();
+        assert_eq!(luma, [255, 0, 0, 128]);
+    }
+
+    #[cfg(feature = "decode_hints")]
+    #[test]
+    fn xrgb32_decodes_the_same_buffer_as_decode_barcode_rgb() {
+        let (pixels, width, height) = rgb_qr_code("rgb packing");
+        let bytes = packed(&pixels);
+
+        let rgb = crate::decode_barcode_rgb(pixels, width, height, None).unwrap();
+        let raw = crate::decode_raw(
+            &bytes,
+            width,
+            height,
+            None,
+            PixelFormat::Xrgb32,
+            &mut crate::decode_hints::DecodeHintDictionary::new(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(rgb.text(), "rgb packing");
+        assert_eq!(raw.text(), rgb.text());
+    }
+}
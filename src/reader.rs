@@ -0,0 +1,418 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+
+use rxing::aztec::AztecReader;
+use rxing::common::{BitArray, BitMatrix, DetectorRXingResult, LineOrientation, Result};
+use rxing::datamatrix::DataMatrixReader;
+use rxing::maxicode::MaxiCodeReader;
+use rxing::oned::cpp::ODReader;
+use rxing::oned::{MultiFormatOneDReader, OneDReader};
+use rxing::pdf417::PDF417Reader;
+use rxing::qrcode::cpp_port::QrReader;
+use rxing::qrcode::detector::Detector as QrDetector;
+use rxing::qrcode::QRCodeReader;
+use rxing::{
+    BarcodeFormat, Binarizer, BinaryBitmap, DecodeHints, Exceptions, Point, RXingResult,
+    RXingResultMetadataType, RXingResultMetadataValue, Reader,
+};
+
+use crate::deadline::Deadline;
+
+pub(crate) const ONE_D_FORMATS: [BarcodeFormat; 12] = [
+    BarcodeFormat::UPC_A,
+    BarcodeFormat::UPC_E,
+    BarcodeFormat::EAN_13,
+    BarcodeFormat::EAN_8,
+    BarcodeFormat::CODABAR,
+    BarcodeFormat::CODE_39,
+    BarcodeFormat::CODE_93,
+    BarcodeFormat::CODE_128,
+    BarcodeFormat::ITF,
+    BarcodeFormat::RSS_14,
+    BarcodeFormat::RSS_EXPANDED,
+    BarcodeFormat::TELEPEN,
+];
+
+/// The smallest MaxiCode symbol is 30 modules wide, so it can't fit in fewer pixels. The MaxiCode
+/// detector also scans for a very long time, or forever, over images narrower than its search
+/// window, so it must not be given them.
+const MAXICODE_MIN_SIZE: usize = 30;
+
+/// Runs the same readers in the same order as rxing's `MultiFormatReader`, but keeps the reason
+/// they failed.
+///
+/// `MultiFormatReader` reports every failure as not found. When no reader succeeds this returns
+/// the most specific error any of them gave, so a damaged symbol is reported as a checksum or
+/// format error rather than as no barcode at all.
+///
+/// Each reader only starts if `deadline` hasn't passed, so a decode that runs out of time skips
+/// the readers it hasn't got to yet. The 1D readers also stop at the next row they scan, and
+/// their quarter turn when trying harder is a separate step.
+#[derive(Default)]
+pub(crate) struct DiagnosticReader {
+    hints: DecodeHints,
+    possible_formats: HashSet<BarcodeFormat>,
+    one_d_reader: MultiFormatOneDReader,
+    deadline: Deadline,
+}
+
+impl DiagnosticReader {
+    pub(crate) fn new(hints: &DecodeHints, deadline: &Deadline) -> Self {
+        let mut reader = Self::with_deadline(deadline);
+        reader.set_hints(hints);
+        reader
+    }
+
+    /// A reader that stops when `deadline` passes, for wrapping in rxing's multi and filtered
+    /// readers, which set the hints themselves
+    pub(crate) fn with_deadline(deadline: &Deadline) -> Self {
+        Self {
+            deadline: deadline.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Set up the readers for `hints`, later calls to `decode_with_state` reuse them
+    pub(crate) fn set_hints(&mut self, hints: &DecodeHints) {
+        self.hints.clone_from(hints);
+        self.possible_formats = hints.PossibleFormats.clone().unwrap_or_default();
+        self.one_d_reader = MultiFormatOneDReader::new(hints);
+    }
+
+    /// Decode with the hints from the last `set_hints` call
+    pub(crate) fn decode_with_state<B: Binarizer>(
+        &mut self,
+        image: &mut BinaryBitmap<B>,
+    ) -> Result<RXingResult> {
+        let mut failure = Failure::default();
+        if let ControlFlow::Break(result) = self.decode_formats(image, &mut failure) {
+            return Ok(result);
+        }
+        if matches!(self.hints.AlsoInverted, Some(true)) && !self.deadline.passed() {
+            image.get_black_matrix_mut().flip_self();
+            if let ControlFlow::Break(mut result) = self.decode_formats(image, &mut failure) {
+                result.putMetadata(
+                    RXingResultMetadataType::IS_INVERTED,
+                    RXingResultMetadataValue::IsInverted(true),
+                );
+                return Ok(result);
+            }
+        }
+        Err(failure.into_error())
+    }
+
+    fn wants(&self, format: BarcodeFormat) -> bool {
+        self.possible_formats.is_empty() || self.possible_formats.contains(&format)
+    }
+
+    fn decode_formats<B: Binarizer>(
+        &mut self,
+        image: &mut BinaryBitmap<B>,
+        failure: &mut Failure,
+    ) -> ControlFlow<RXingResult> {
+        let (hints, deadline) = (&self.hints, &self.deadline);
+        let try_harder = matches!(hints.TryHarder, Some(true));
+        let one_d = ONE_D_FORMATS.iter().any(|format| self.wants(*format));
+
+        // 1D readers are quick, so they go first unless trying harder
+        if one_d && !try_harder {
+            failure.attempt(
+                deadline.run(|| decode_one_d(&mut self.one_d_reader, image, hints, deadline)),
+            )?;
+        }
+        // only the older QR reader reports candidate points, so it goes first when they're wanted
+        let points_wanted = hints.NeedResultPointCallback.is_some();
+        if points_wanted && self.wants(BarcodeFormat::QR_CODE) {
+            failure.attempt(deadline.run(|| decode_legacy_qr(image, hints)))?;
+        }
+        if self.wants(BarcodeFormat::QR_CODE)
+            || self.wants(BarcodeFormat::MICRO_QR_CODE)
+            || self.wants(BarcodeFormat::RECTANGULAR_MICRO_QR_CODE)
+        {
+            failure.attempt(deadline.run(|| QrReader.decode_with_hints(image, hints)))?;
+        }
+        if !points_wanted && self.wants(BarcodeFormat::QR_CODE) {
+            failure.attempt(deadline.run(|| decode_legacy_qr(image, hints)))?;
+        }
+        if self.wants(BarcodeFormat::DATA_MATRIX) {
+            failure.attempt(deadline.run(|| DataMatrixReader.decode_with_hints(image, hints)))?;
+        }
+        if self.wants(BarcodeFormat::AZTEC) {
+            failure.attempt(deadline.run(|| AztecReader.decode_with_hints(image, hints)))?;
+        }
+        if self.wants(BarcodeFormat::PDF_417) {
+            let (width, height) = (image.get_width() as f32, image.get_height() as f32);
+            failure.attempt(deadline.run(|| {
+                PDF417Reader
+                    .decode_with_hints(image, hints)
+                    .map(|result| unrotate_pdf417(result, width, height))
+            }))?;
+        }
+        if self.wants(BarcodeFormat::MAXICODE)
+            && image.get_width().min(image.get_height()) >= MAXICODE_MIN_SIZE
+        {
+            failure.attempt(
+                deadline.run(|| MaxiCodeReader::default().decode_with_hints(image, hints)),
+            )?;
+        }
+        if self.wants(BarcodeFormat::DXFilmEdge) {
+            failure
+                .attempt(deadline.run(|| ODReader::new(hints).decode_with_hints(image, hints)))?;
+        }
+        if one_d && try_harder {
+            failure.attempt(
+                deadline.run(|| decode_one_d(&mut self.one_d_reader, image, hints, deadline)),
+            )?;
+            // rxing's 1D reader tries a quarter turn itself when trying harder, doing it here
+            // checks the deadline in between
+            if image.is_rotate_supported() {
+                let width = image.get_width();
+                failure.attempt(deadline.run(|| {
+                    let rotated = image.rotate_counter_clockwise();
+                    decode_one_d(&mut self.one_d_reader, &rotated, hints, deadline)
+                        .map(|result| unrotate(result, width))
+                }))?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl Reader for DiagnosticReader {
+    fn decode<B: Binarizer>(&mut self, image: &mut BinaryBitmap<B>) -> Result<RXingResult> {
+        self.decode_with_hints(image, &DecodeHints::default())
+    }
+
+    fn decode_with_hints<B: Binarizer>(
+        &mut self,
+        image: &mut BinaryBitmap<B>,
+        hints: &DecodeHints,
+    ) -> Result<RXingResult> {
+        self.set_hints(hints);
+        self.decode_with_state(image)
+    }
+
+    fn reset(&mut self) {
+        self.one_d_reader.reset();
+    }
+}
+
+/// Scan the rows of `image` for a 1D barcode, without the quarter turn rxing adds when trying
+/// harder, and stop at the next row once `deadline` has passed
+fn decode_one_d<B: Binarizer>(
+    reader: &mut MultiFormatOneDReader,
+    image: &BinaryBitmap<B>,
+    hints: &DecodeHints,
+    deadline: &Deadline,
+) -> Result<RXingResult> {
+    reader._do_decode(
+        &mut BinaryBitmap::new(DeadlineRows {
+            binarizer: image.get_binarizer(),
+            deadline,
+        }),
+        hints,
+    )
+}
+
+/// A borrowed binarizer that stops handing out rows once the deadline has passed. The 1D readers
+/// skip rows that can't be binarized, so they run through the rest of the image without reading
+/// any of it.
+struct DeadlineRows<'a, B> {
+    binarizer: &'a B,
+    deadline: &'a Deadline,
+}
+
+impl<B: Binarizer> Binarizer for DeadlineRows<'_, B> {
+    type Source = B::Source;
+
+    fn get_luminance_source(&self) -> &Self::Source {
+        self.binarizer.get_luminance_source()
+    }
+
+    fn get_black_row(&self, y: usize) -> Result<Cow<'_, BitArray>> {
+        self.deadline.check()?;
+        self.binarizer.get_black_row(y)
+    }
+
+    fn get_black_row_from_matrix(&self, y: usize) -> Result<Cow<'_, BitArray>> {
+        self.deadline.check()?;
+        self.binarizer.get_black_row_from_matrix(y)
+    }
+
+    fn get_black_matrix(&self) -> Result<&BitMatrix> {
+        self.binarizer.get_black_matrix()
+    }
+
+    fn get_black_line(&self, l: usize, lt: LineOrientation) -> Result<Cow<'_, BitArray>> {
+        self.binarizer.get_black_line(l, lt)
+    }
+
+    fn create_binarizer(&self, _source: Self::Source) -> Self {
+        unreachable!("the 1D readers never crop or rotate the image they are given")
+    }
+
+    fn get_width(&self) -> usize {
+        self.binarizer.get_width()
+    }
+
+    fn get_height(&self) -> usize {
+        self.binarizer.get_height()
+    }
+}
+
+/// Map the points of a result from an image turned a quarter counter-clockwise back into the
+/// original one, `width` pixels wide, and add the rotation to its orientation as rxing's readers
+/// do
+pub(crate) fn unrotate(mut result: RXingResult, width: usize) -> RXingResult {
+    for point in result.getPointsMut() {
+        // rotating moved (x, y) to (y, width - 1 - x)
+        (point.x, point.y) = (width as f32 - 1.0 - point.y, point.x);
+    }
+    let orientation = match result
+        .getRXingResultMetadata()
+        .get(&RXingResultMetadataType::ORIENTATION)
+    {
+        Some(RXingResultMetadataValue::Orientation(orientation)) => *orientation,
+        _ => 0,
+    };
+    result.putMetadata(
+        RXingResultMetadataType::ORIENTATION,
+        RXingResultMetadataValue::Orientation((orientation + 270) % 360),
+    );
+    result
+}
+
+/// Decode with rxing's older QR reader, reporting the symbol's corners like the newer one.
+///
+/// The older reader gives the centers of the finder patterns and alignment pattern instead, so
+/// the symbol is located again to work out where its corners are.
+fn decode_legacy_qr<B: Binarizer>(
+    image: &mut BinaryBitmap<B>,
+    hints: &DecodeHints,
+) -> Result<RXingResult> {
+    let mut result = QRCodeReader.decode_with_hints(image, hints)?;
+    // pure barcodes are read without locating them, and have no points
+    if result.getPoints().len() >= 3 {
+        // the points were reported to any callback the first time round
+        let hints = DecodeHints {
+            NeedResultPointCallback: None,
+            ..hints.clone()
+        };
+        if let Ok(detected) = QrDetector::new(image.get_black_matrix()).detect_with_hints(&hints) {
+            if let Some(corners) = finder_corners(detected.getPoints(), detected.getBits().width())
+            {
+                result.replace_points(corners.to_vec());
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The corners of a QR code `dimension` modules wide from the centers of its bottom-left,
+/// top-left and top-right finder patterns, which sit 3.5 modules in from each corner
+fn finder_corners(finders: &[Point], dimension: u32) -> Option<[Point; 4]> {
+    let [bottom_left, top_left, top_right, ..] = *finders else {
+        return None;
+    };
+    let spacing = dimension.checked_sub(7)? as f32;
+    let across = (top_right - top_left) / spacing * 3.5;
+    let down = (bottom_left - top_left) / spacing * 3.5;
+    let top_left = top_left - across - down;
+    let top_right = top_right + across - down;
+    let bottom_left = bottom_left - across + down;
+    // perspective is ignored, the symbol is treated as a parallelogram
+    let bottom_right = top_right + bottom_left - top_left;
+    Some([top_left, top_right, bottom_right, bottom_left])
+}
+
+/// Map the points of a PDF 417 result back into the image it was found in.
+///
+/// The PDF 417 detector searches rotated copies of the image and reports points in whichever
+/// copy it found the symbol in, recording the rotation as the result's orientation.
+fn unrotate_pdf417(mut result: RXingResult, width: f32, height: f32) -> RXingResult {
+    let rotation = match result
+        .getRXingResultMetadata()
+        .get(&RXingResultMetadataType::ORIENTATION)
+    {
+        Some(RXingResultMetadataValue::Orientation(rotation)) => *rotation,
+        _ => 0,
+    };
+    for point in result.getPointsMut() {
+        let Point { x, y } = *point;
+        // rotating by 90 moves (x, y) to (y, width - 1 - x), and by 270 to (height - 1 - y, x)
+        (point.x, point.y) = match rotation {
+            90 => (width - 1.0 - y, x),
+            180 => (width - 1.0 - x, height - 1.0 - y),
+            270 => (y, height - 1.0 - x),
+            _ => (x, y),
+        };
+    }
+    result
+}
+
+/// The most specific error seen so far
+#[derive(Default)]
+pub(crate) struct Failure(Option<Exceptions>);
+
+impl Failure {
+    /// Break with a successful result, or remember the error and carry on with the next reader
+    pub(crate) fn attempt<T>(&mut self, result: Result<T>) -> ControlFlow<T> {
+        match result {
+            Ok(result) => ControlFlow::Break(result),
+            Err(error) => {
+                if self.0.as_ref().is_none_or(|seen| rank(&error) > rank(seen)) {
+                    self.0 = Some(error);
+                }
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    pub(crate) fn into_error(self) -> Exceptions {
+        self.0.unwrap_or(Exceptions::NOT_FOUND)
+    }
+}
+
+/// How far a reader got before failing, a checksum error means a symbol was found and read
+fn rank(error: &Exceptions) -> u8 {
+    match error {
+        Exceptions::NotFoundException(_) | Exceptions::UnsupportedOperationException(_) => 0,
+        Exceptions::ChecksumException(_) | Exceptions::ReedSolomonException(_) => 3,
+        Exceptions::FormatException(_) => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rxing::Writer;
+
+    use super::*;
+
+    /// A Code 128 symbol turned a quarter clockwise, so its bars run across the image
+    fn vertical_code_128(text: &str) -> (Vec<u8>, u32, u32) {
+        let matrix = rxing::oned::Code128Writer
+            .encode(text, &BarcodeFormat::CODE_128, 240, 60)
+            .unwrap();
+        let (width, height) = (matrix.height(), matrix.width());
+        let luma = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| if matrix.get(y, width - 1 - x) { 0 } else { 255 })
+            .collect();
+        (luma, width, height)
+    }
+
+    #[test]
+    fn trying_harder_reads_linear_barcodes_turned_a_quarter() {
+        let (luma, width, height) = vertical_code_128("QUARTER TURN");
+        let result = crate::decode_barcode(luma, width, height, Some(true), None).unwrap();
+        assert_eq!(result.text(), "QUARTER TURN");
+        let points = result.result_points();
+        // both ends of the scanned line are mapped back onto the vertical symbol
+        assert_eq!(points.len(), 4);
+        assert!((points[0] - points[2]).abs() < 1.0);
+        assert!(points[0] < width as f32 && points[1] < height as f32);
+        assert!(points[3] < height as f32 && points[1] != points[3]);
+    }
+}
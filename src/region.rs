@@ -0,0 +1,63 @@
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "decode_hints")]
+use crate::error::BarcodeError;
+#[cfg(feature = "decode_hints")]
+use crate::luma_source::check_pixel_count;
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+/// A rectangular area of an image, in pixels, with the origin at the top-left
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[wasm_bindgen]
+impl Region {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+impl Region {
+    #[cfg(feature = "decode_hints")]
+    /// Copy this region out of a tightly packed luma 8 image of `width` x `height` pixels
+    pub(crate) fn crop_luma(
+        &self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, BarcodeError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(BarcodeError::illegal_argument("region must not be empty"));
+        }
+        if self.x as u64 + self.width as u64 > width as u64
+            || self.y as u64 + self.height as u64 > height as u64
+        {
+            return Err(BarcodeError::illegal_argument(format!(
+                "region {}x{} at ({}, {}) does not fit in a {width}x{height} image",
+                self.width, self.height, self.x, self.y
+            )));
+        }
+        check_pixel_count(data.len(), width, height)?;
+
+        let mut cropped = Vec::with_capacity(self.width as usize * self.height as usize);
+        for row in data
+            .chunks_exact(width as usize)
+            .skip(self.y as usize)
+            .take(self.height as usize)
+        {
+            cropped.extend_from_slice(&row[self.x as usize..(self.x + self.width) as usize]);
+        }
+        Ok(cropped)
+    }
+}
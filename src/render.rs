@@ -0,0 +1,460 @@
+use std::fmt::Write;
+
+use rxing::common::BitMatrix;
+use wasm_bindgen::prelude::*;
+
+use crate::error::BarcodeError;
+
+const DEFAULT_FOREGROUND: &str = "#000000";
+const DEFAULT_BACKGROUND: &str = "#FFFFFF";
+
+#[wasm_bindgen]
+#[derive(Clone)]
+/// Options controlling how an encoded barcode is drawn
+pub struct RenderOptions {
+    quiet_zone: u32,
+    scale: u32,
+    dpi: u32,
+    foreground: String,
+    background: String,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            quiet_zone: 0,
+            scale: 0,
+            dpi: 0,
+            foreground: DEFAULT_FOREGROUND.to_owned(),
+            background: DEFAULT_BACKGROUND.to_owned(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl RenderOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RenderOptions {
+        RenderOptions::default()
+    }
+
+    /// Blank units added on every side of the symbol, defaults to 0
+    #[wasm_bindgen(getter)]
+    pub fn quiet_zone(&self) -> u32 {
+        self.quiet_zone
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_quiet_zone(&mut self, quiet_zone: u32) {
+        self.quiet_zone = quiet_zone;
+    }
+
+    /// Whole pixels drawn for each unit of the encoded matrix. 0, the default, draws one pixel
+    /// per unit, or fits the largest whole scale into the target when drawing to a canvas.
+    #[wasm_bindgen(getter)]
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale;
+    }
+
+    /// Resolution recorded in PNG output, in dots per inch. 0, the default, records none.
+    #[wasm_bindgen(getter)]
+    pub fn dpi(&self) -> u32 {
+        self.dpi
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_dpi(&mut self, dpi: u32) {
+        self.dpi = dpi;
+    }
+
+    /// Color of set modules, defaults to "#000000"
+    #[wasm_bindgen(getter)]
+    pub fn foreground(&self) -> String {
+        self.foreground.to_owned()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_foreground(&mut self, foreground: String) {
+        self.foreground = foreground;
+    }
+
+    /// Color of unset modules and the quiet zone, defaults to "#FFFFFF".
+    /// An empty string leaves the background transparent.
+    #[wasm_bindgen(getter)]
+    pub fn background(&self) -> String {
+        self.background.to_owned()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_background(&mut self, background: String) {
+        self.background = background;
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+/// A rendered barcode as raw RGBA pixels, four bytes per pixel, row by row
+pub struct RgbaImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl RgbaImage {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn data(&self) -> Vec<u8> {
+        self.data.to_vec()
+    }
+}
+
+impl RgbaImage {
+    #[cfg(feature = "png_output")]
+    pub(crate) fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub(crate) fn into_image_data(self) -> Result<web_sys::ImageData, JsValue> {
+        web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+            wasm_bindgen::Clamped(&self.data),
+            self.width,
+            self.height,
+        )
+    }
+}
+
+impl RenderOptions {
+    pub(crate) fn effective_scale(&self) -> u32 {
+        self.scale.max(1)
+    }
+
+    /// The size, in units, of a `BitMatrix` with the quiet zone added on every side
+    fn padded_size(&self, matrix: &BitMatrix) -> (u64, u64) {
+        let padding = 2 * self.quiet_zone as u64;
+        (
+            matrix.width() as u64 + padding,
+            matrix.height() as u64 + padding,
+        )
+    }
+
+    /// The size, in pixels, of a `BitMatrix` rendered with these options
+    pub(crate) fn rendered_size(&self, matrix: &BitMatrix) -> Result<(u32, u32), BarcodeError> {
+        let scale = self.effective_scale() as u64;
+        let (width, height) = self.padded_size(matrix);
+        match (u32::try_from(width * scale), u32::try_from(height * scale)) {
+            (Ok(width), Ok(height)) => Ok((width, height)),
+            _ => Err(BarcodeError::illegal_argument(format!(
+                "a {width}x{height} symbol at a scale of {scale} is too large to render"
+            ))),
+        }
+    }
+
+    /// The largest whole scale at which a `BitMatrix` still fits in the given area
+    pub(crate) fn fit_scale(&self, matrix: &BitMatrix, width: u32, height: u32) -> u32 {
+        let (full_width, full_height) = self.padded_size(matrix);
+        (width as u64 / full_width.max(1))
+            .min(height as u64 / full_height.max(1))
+            .max(1) as u32
+    }
+}
+
+/// Render a `BitMatrix` into RGBA pixels at the size given by `options.rendered_size`.
+pub(crate) fn bit_matrix_to_rgba(
+    matrix: &BitMatrix,
+    options: &RenderOptions,
+) -> Result<RgbaImage, BarcodeError> {
+    let (width, height) = options.rendered_size(matrix)?;
+    bit_matrix_to_rgba_sized(matrix, options, width, height)
+}
+
+/// Render a `BitMatrix` into an RGBA image of exactly `width` x `height` pixels.
+///
+/// The symbol, with its quiet zone and scale, is centered in the image and the
+/// remaining area is filled with the background color.
+pub(crate) fn bit_matrix_to_rgba_sized(
+    matrix: &BitMatrix,
+    options: &RenderOptions,
+    width: u32,
+    height: u32,
+) -> Result<RgbaImage, BarcodeError> {
+    let foreground = parse_color(&options.foreground)?;
+    let background = parse_color(&options.background)?;
+
+    let mut data = allocate_image(width, height, 4)?;
+    for_each_pixel(matrix, options, width, height, |_, set| {
+        data.extend_from_slice(if set { &foreground } else { &background });
+    });
+
+    Ok(RgbaImage {
+        width,
+        height,
+        data,
+    })
+}
+
+/// An empty buffer with room for a `width` x `height` image of `bytes_per_pixel`.
+///
+/// Running out of memory aborts the wasm instance, so large images are refused up front.
+fn allocate_image(width: u32, height: u32, bytes_per_pixel: u64) -> Result<Vec<u8>, BarcodeError> {
+    let mut data = Vec::new();
+    usize::try_from(width as u64 * height as u64 * bytes_per_pixel)
+        .ok()
+        .and_then(|len| data.try_reserve_exact(len).ok())
+        .ok_or_else(|| {
+            BarcodeError::illegal_argument(format!(
+                "not enough memory to render a {width}x{height} image"
+            ))
+        })?;
+    Ok(data)
+}
+
+/// Walk every pixel of a `width` x `height` image with the symbol centered in it,
+/// calling `f` with the pixel's x coordinate and whether it falls on a set module.
+fn for_each_pixel(
+    matrix: &BitMatrix,
+    options: &RenderOptions,
+    width: u32,
+    height: u32,
+    mut f: impl FnMut(u32, bool),
+) {
+    // the symbol can be larger than the image, which crops it, so this is done in 128 bits
+    let scale = options.effective_scale() as i128;
+    let (padded_width, padded_height) = options.padded_size(matrix);
+    let quiet_zone = options.quiet_zone as i128 * scale;
+    let origin_x = (width as i128 - padded_width as i128 * scale) / 2 + quiet_zone;
+    let origin_y = (height as i128 - padded_height as i128 * scale) / 2 + quiet_zone;
+
+    for y in 0..height {
+        let module_y = (y as i128 - origin_y).div_euclid(scale);
+        for x in 0..width {
+            let module_x = (x as i128 - origin_x).div_euclid(scale);
+            let set = match (u32::try_from(module_x), u32::try_from(module_y)) {
+                (Ok(module_x), Ok(module_y)) => matrix.try_get(module_x, module_y).unwrap_or(false),
+                _ => false,
+            };
+            f(x, set);
+        }
+    }
+}
+
+#[cfg(feature = "png_output")]
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Pixel layout of PNG output
+pub enum PngColorType {
+    /// 1 bit per pixel, set modules are black and everything else is white. Colors are ignored.
+    Grayscale1,
+
+    /// 8 bits per channel RGBA, using the foreground and background colors, including transparency.
+    Rgba8,
+}
+
+/// Render a `BitMatrix` as PNG file bytes at the size given by `options.rendered_size`.
+///
+/// When `options.dpi` is set a pHYs chunk is written so the image prints at the intended size.
+#[cfg(feature = "png_output")]
+pub(crate) fn bit_matrix_to_png(
+    matrix: &BitMatrix,
+    options: &RenderOptions,
+    color_type: PngColorType,
+) -> Result<Vec<u8>, BarcodeError> {
+    let (width, height) = options.rendered_size(matrix)?;
+
+    let (png_color, png_depth, data) = match color_type {
+        PngColorType::Grayscale1 => {
+            let row_bytes = width.div_ceil(8);
+            let mut data = allocate_image(row_bytes, height, 1)?;
+            let row_bytes = row_bytes as usize;
+            data.resize(row_bytes * height as usize, 0);
+            let mut row = 0;
+            for_each_pixel(matrix, options, width, height, |x, set| {
+                if !set {
+                    data[row * row_bytes + x as usize / 8] |= 0x80 >> (x % 8);
+                }
+                if x + 1 == width {
+                    row += 1;
+                }
+            });
+            (png::ColorType::Grayscale, png::BitDepth::One, data)
+        }
+        PngColorType::Rgba8 => (
+            png::ColorType::Rgba,
+            png::BitDepth::Eight,
+            bit_matrix_to_rgba(matrix, options)?.into_data(),
+        ),
+    };
+
+    let mut png_bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+    encoder.set_color(png_color);
+    encoder.set_depth(png_depth);
+    if options.dpi > 0 {
+        // pHYs is expressed in pixels per meter
+        let pixels_per_meter = (options.dpi as f64 / 0.0254).round() as u32;
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: pixels_per_meter,
+            yppu: pixels_per_meter,
+            unit: png::Unit::Meter,
+        }));
+    }
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|err| BarcodeError::illegal_argument(format!("couldn't write png: {err}")))?;
+
+    Ok(png_bytes)
+}
+
+/// Parse a CSS style hex color (`#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`) into RGBA.
+/// An empty string or `transparent` is fully transparent.
+pub(crate) fn parse_color(color: &str) -> Result<[u8; 4], BarcodeError> {
+    let color = color.trim();
+    if color.is_empty() || color.eq_ignore_ascii_case("transparent") {
+        return Ok([0, 0, 0, 0]);
+    }
+
+    let invalid = || {
+        BarcodeError::illegal_argument(format!(
+            "unsupported color '{color}', expected a hex color such as #RRGGBB"
+        ))
+    };
+    let Some(hex) = color.strip_prefix('#') else {
+        return Err(invalid());
+    };
+    if !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let digits = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|v| (v * 17) as u8))
+            .collect::<Option<Vec<u8>>>(),
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>(),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+
+    Ok([
+        digits[0],
+        digits[1],
+        digits[2],
+        digits.get(3).copied().unwrap_or(0xFF),
+    ])
+}
+
+/// Render a `BitMatrix` as a standalone SVG document.
+///
+/// Horizontal runs of set modules are merged, and runs repeated on consecutive
+/// rows are merged into a single rectangle, so the output stays compact even
+/// for large, scaled symbols. The quiet zone is added on every side, in the
+/// same units as the matrix, and the scale only affects the document size.
+pub(crate) fn bit_matrix_to_svg(matrix: &BitMatrix, options: &RenderOptions) -> String {
+    let quiet_zone = options.quiet_zone as u64;
+    let height = matrix.height();
+    let (full_width, full_height) = options.padded_size(matrix);
+    // only written into the document, so a size too large for an image is still fine here
+    let scale = options.effective_scale() as u64;
+    let (pixel_width, pixel_height) = (
+        full_width.saturating_mul(scale),
+        full_height.saturating_mul(scale),
+    );
+
+    let mut path = String::new();
+    let mut y = 0;
+    while y < height {
+        let runs = row_runs(matrix, y);
+
+        // collapse identical rows so vertical repeats become one rectangle
+        let mut run_height = 1;
+        while y + run_height < height && row_runs(matrix, y + run_height) == runs {
+            run_height += 1;
+        }
+
+        for (start, length) in runs {
+            let _ = write!(
+                path,
+                "M{},{}h{}v{}h-{}z",
+                start as u64 + quiet_zone,
+                y as u64 + quiet_zone,
+                length,
+                run_height,
+                length
+            );
+        }
+
+        y += run_height;
+    }
+
+    let mut svg = String::with_capacity(path.len() + 256);
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" viewBox=\"0 0 {full_width} {full_height}\" width=\"{pixel_width}\" height=\"{pixel_height}\" shape-rendering=\"crispEdges\">"
+    );
+    if !options.background.is_empty() {
+        let _ = write!(
+            svg,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            escape_attribute(&options.background)
+        );
+    }
+    if !path.is_empty() {
+        let _ = write!(
+            svg,
+            "<path fill=\"{}\" d=\"{}\"/>",
+            escape_attribute(&options.foreground),
+            path
+        );
+    }
+    svg.push_str("</svg>");
+
+    svg
+}
+
+/// Find every run of set modules in a row as `(start, length)` pairs
+fn row_runs(matrix: &BitMatrix, y: u32) -> Vec<(u32, u32)> {
+    let mut runs = Vec::new();
+    let mut x = 0;
+    while x < matrix.width() {
+        if !matrix.get(x, y) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < matrix.width() && matrix.get(x, y) {
+            x += 1;
+        }
+        runs.push((start, x - start));
+    }
+    runs
+}
+
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
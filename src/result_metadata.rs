@@ -0,0 +1,145 @@
+use rxing::pdf417::PDF417RXingResultMetadata;
+use rxing::{RXingResultMetadataType, RXingResultMetadataValue};
+use wasm_bindgen::prelude::*;
+
+use crate::BarcodeResult;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_RESULT_METADATA: &'static str = r#"
+/** Structured append position of a symbol, as reported by the reader that found it */
+export interface StructuredAppend {
+    sequence: number;
+    parity?: number;
+}
+
+/** The Macro PDF417 control block of a symbol. Optional fields are only set when present */
+export interface Pdf417ExtraMetadata {
+    segmentIndex: number;
+    fileId: string;
+    lastSegment: boolean;
+    segmentCount?: number;
+    sender?: string;
+    addressee?: string;
+    fileName?: string;
+    fileSize?: number;
+    timestamp?: number;
+    checksum?: number;
+}
+"#;
+
+#[wasm_bindgen]
+impl BarcodeResult {
+    /// Rotation of the symbol in degrees when it was found, for the formats that report it
+    pub fn orientation(&self) -> Option<i32> {
+        match self.metadata(RXingResultMetadataType::ORIENTATION)? {
+            RXingResultMetadataValue::Orientation(orientation) => Some(*orientation),
+            _ => None,
+        }
+    }
+
+    /// The raw bytes of each byte mode segment, for QR, Data Matrix, Aztec and PDF 417
+    pub fn byte_segments(&self) -> Vec<js_sys::Uint8Array> {
+        match self.metadata(RXingResultMetadataType::BYTE_SEGMENTS) {
+            Some(RXingResultMetadataValue::ByteSegments(segments)) => segments
+                .iter()
+                .map(|segment| js_sys::Uint8Array::from(segment.as_slice()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The error correction level, such as "M" for QR codes or the percentage for Aztec
+    pub fn error_correction_level(&self) -> Option<String> {
+        match self.metadata(RXingResultMetadataType::ERROR_CORRECTION_LEVEL)? {
+            RXingResultMetadataValue::ErrorCorrectionLevel(level) => Some(level.to_owned()),
+            _ => None,
+        }
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "StructuredAppend | undefined")]
+    /// The symbol's place in a structured append sequence, undefined if it isn't part of one
+    pub fn structured_append(&self) -> JsValue {
+        let sequence = match self.metadata(RXingResultMetadataType::STRUCTURED_APPEND_SEQUENCE) {
+            Some(RXingResultMetadataValue::StructuredAppendSequence(sequence))
+                if *sequence >= 0 =>
+            {
+                *sequence
+            }
+            _ => return JsValue::UNDEFINED,
+        };
+        let object = js_sys::Object::new();
+        set(&object, "sequence", sequence.into());
+        if let Some(RXingResultMetadataValue::StructuredAppendParity(parity)) =
+            self.metadata(RXingResultMetadataType::STRUCTURED_APPEND_PARITY)
+        {
+            set(&object, "parity", (*parity).into());
+        }
+        object.into()
+    }
+
+    /// Whether the symbol was read from a mirror image
+    pub fn is_mirrored(&self) -> bool {
+        matches!(
+            self.metadata(RXingResultMetadataType::IS_MIRRORED),
+            Some(RXingResultMetadataValue::IsMirrored(true))
+        )
+    }
+
+    /// Whether the symbol was read light on dark
+    pub fn is_inverted(&self) -> bool {
+        matches!(
+            self.metadata(RXingResultMetadataType::IS_INVERTED),
+            Some(RXingResultMetadataValue::IsInverted(true))
+        )
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "Pdf417ExtraMetadata | undefined")]
+    /// The Macro PDF417 control block, undefined for other formats and plain PDF 417 symbols
+    pub fn pdf417_extra(&self) -> JsValue {
+        match self.metadata(RXingResultMetadataType::PDF417_EXTRA_METADATA) {
+            Some(RXingResultMetadataValue::Pdf417ExtraMetadata(extra)) => pdf417_extra(extra),
+            _ => JsValue::UNDEFINED,
+        }
+    }
+}
+
+impl BarcodeResult {
+    fn metadata(&self, key: RXingResultMetadataType) -> Option<&RXingResultMetadataValue> {
+        self.result_metadata.get(&key)
+    }
+}
+
+fn pdf417_extra(extra: &PDF417RXingResultMetadata) -> JsValue {
+    let object = js_sys::Object::new();
+    set(&object, "segmentIndex", extra.getSegmentIndex().into());
+    set(&object, "fileId", extra.getFileId().into());
+    set(&object, "lastSegment", extra.isLastSegment().into());
+    // rxing marks the optional fields that weren't in the control block with -1 or an empty value
+    if extra.getSegmentCount() >= 0 {
+        set(&object, "segmentCount", extra.getSegmentCount().into());
+    }
+    for (name, value) in [
+        ("sender", extra.getSender()),
+        ("addressee", extra.getAddressee()),
+        ("fileName", extra.getFileName()),
+    ] {
+        if !value.is_empty() {
+            set(&object, name, value.into());
+        }
+    }
+    for (name, value) in [
+        ("fileSize", extra.getFileSize()),
+        ("timestamp", extra.getTimestamp()),
+        ("checksum", extra.getChecksum().into()),
+    ] {
+        if value >= 0 {
+            set(&object, name, (value as f64).into());
+        }
+    }
+    object.into()
+}
+
+pub(crate) fn set(object: &js_sys::Object, key: &str, value: JsValue) {
+    // setting a property on a plain object cannot fail
+    let _ = js_sys::Reflect::set(object, &JsValue::from_str(key), &value);
+}
@@ -0,0 +1,445 @@
+use wasm_bindgen::prelude::*;
+
+use crate::binarizer::Binarizer;
+use crate::decode_hints::DecodeHintDictionary;
+use crate::decoder::Decoder;
+use crate::error::BarcodeError;
+use crate::pixel_format::PixelFormat;
+use crate::result_metadata::set;
+use crate::BarcodeResult;
+
+/// The most frames a `Scanner` can look back over, one bit of a `u32` each
+const MAX_WINDOW: u32 = 32;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_SCANNED_SYMBOL: &'static str = r#"
+/** A symbol a `Scanner` has read recently, from `Scanner.symbols` */
+export interface ScannedSymbol {
+    /** The latest read of the symbol, with its position in that frame */
+    result: BarcodeResult;
+    /** How many of the frames in the window it was read in */
+    hits: number;
+    /** Whether it was read in the latest frame */
+    inView: boolean;
+    /** Whether it has been reported */
+    confirmed: boolean;
+    /** The time of the latest frame it was read in */
+    lastSeen: number;
+}
+"#;
+
+#[wasm_bindgen]
+#[derive(Clone)]
+/// Settings for a `Scanner`
+pub struct ScannerOptions {
+    confirmations: u32,
+    window: u32,
+    cooldown_ms: f64,
+    multiple: bool,
+}
+
+impl Default for ScannerOptions {
+    fn default() -> Self {
+        Self {
+            confirmations: 3,
+            window: 0,
+            cooldown_ms: 2000.0,
+            multiple: false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ScannerOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ScannerOptions {
+        ScannerOptions::default()
+    }
+
+    /// How many frames in the window a symbol must be read in before it is reported, defaults
+    /// to 3
+    #[wasm_bindgen(getter)]
+    pub fn confirmations(&self) -> u32 {
+        self.confirmations
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_confirmations(&mut self, confirmations: u32) {
+        self.confirmations = confirmations;
+    }
+
+    /// How many of the latest frames are counted, at most 32. 0, the default, uses the number of
+    /// confirmations, so a symbol must be read in that many frames in a row.
+    #[wasm_bindgen(getter)]
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_window(&mut self, window: u32) {
+        self.window = window;
+    }
+
+    /// How long a reported symbol must be out of view before it can be reported again, in
+    /// milliseconds, defaults to 2000
+    #[wasm_bindgen(getter)]
+    pub fn cooldown_ms(&self) -> f64 {
+        self.cooldown_ms
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_cooldown_ms(&mut self, cooldown_ms: f64) {
+        self.cooldown_ms = cooldown_ms;
+    }
+
+    /// Read every symbol in each frame rather than only the first one found, defaults to false
+    #[wasm_bindgen(getter)]
+    pub fn multiple(&self) -> bool {
+        self.multiple
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_multiple(&mut self, multiple: bool) {
+        self.multiple = multiple;
+    }
+}
+
+#[wasm_bindgen]
+/// Reads barcodes from successive video frames and reports each symbol once.
+///
+/// A symbol is only reported after it has been read in enough frames of a sliding window, which
+/// filters out misreads that only show up in a single frame. Once reported it is not reported
+/// again until it has been out of view for the cooldown, so holding a code in front of the
+/// camera gives one read.
+pub struct Scanner {
+    decoder: Decoder,
+    confirmations: u32,
+    window: u32,
+    cooldown_ms: f64,
+    multiple: bool,
+    candidates: Vec<Candidate>,
+}
+
+/// A symbol read in at least one recent frame
+struct Candidate {
+    /// The latest read
+    result: BarcodeResult,
+    /// Bit 0 is the latest frame, a set bit means the symbol was read in that frame
+    history: u32,
+    last_seen_ms: f64,
+    reported: bool,
+}
+
+impl Candidate {
+    fn matches(&self, result: &BarcodeResult) -> bool {
+        self.result.format == result.format && self.result.text == result.text
+    }
+
+    fn hits(&self) -> u32 {
+        self.history.count_ones()
+    }
+
+    fn in_view(&self) -> bool {
+        self.history & 1 != 0
+    }
+}
+
+#[wasm_bindgen]
+impl Scanner {
+    /// Create a scanner that decodes frames with a copy of `hints`. `filter_image` and
+    /// `binarizers` are used as in `Decoder`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        hints: &DecodeHintDictionary,
+        options: Option<ScannerOptions>,
+        filter_image: Option<bool>,
+        binarizers: Option<Vec<Binarizer>>,
+    ) -> Result<Scanner, BarcodeError> {
+        let options = options.unwrap_or_default();
+        let window = match options.window {
+            0 => options.confirmations,
+            window => window,
+        };
+        if options.confirmations == 0 || options.confirmations > window || window > MAX_WINDOW {
+            return Err(BarcodeError::illegal_argument(format!(
+                "a scanner needs 1 to {MAX_WINDOW} frames in its window and no more confirmations \
+                 than that, not {} of {window}",
+                options.confirmations
+            )));
+        }
+        if options.cooldown_ms.is_nan() || options.cooldown_ms < 0.0 {
+            return Err(BarcodeError::illegal_argument(
+                "cooldown_ms must be 0 or more",
+            ));
+        }
+        Ok(Scanner {
+            decoder: Decoder::new(hints, filter_image, binarizers),
+            confirmations: options.confirmations,
+            window,
+            cooldown_ms: options.cooldown_ms,
+            multiple: options.multiple,
+            candidates: Vec::new(),
+        })
+    }
+
+    /// Scan a frame of raw pixels in any supported `PixelFormat`, returning the symbols confirmed
+    /// by this frame.
+    ///
+    /// `now_ms` is the time of the frame in milliseconds on any steady clock, such as the
+    /// timestamp passed to a `requestVideoFrameCallback` callback, and defaults to `Date.now()`.
+    /// Frames where nothing can be read still count towards the window.
+    pub fn scan(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        stride: Option<u32>,
+        pixel_format: PixelFormat,
+        now_ms: Option<f64>,
+    ) -> Result<Vec<BarcodeResult>, BarcodeError> {
+        let reads = if self.multiple {
+            self.decoder
+                .decode_multi(data, width, height, stride, pixel_format)
+        } else {
+            self.decoder
+                .decode(data, width, height, stride, pixel_format)
+                .map(|result| vec![result])
+        };
+        let reads = match reads {
+            Ok(reads) => reads,
+            // a frame without a readable symbol is still a frame
+            Err(error) if error.is_unreadable() => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        Ok(self.step(reads, now_ms.unwrap_or_else(js_sys::Date::now)))
+    }
+
+    /// Scan a frame of 8bit luma data, see `scan`
+    pub fn scan_luma(
+        &mut self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        now_ms: Option<f64>,
+    ) -> Result<Vec<BarcodeResult>, BarcodeError> {
+        self.scan(data, width, height, None, PixelFormat::Gray8, now_ms)
+    }
+
+    /// Scan a frame from an `ImageData`, such as one from `getImageData` on a canvas the video is
+    /// drawn to, see `scan`
+    pub fn scan_image_data(
+        &mut self,
+        image_data: &web_sys::ImageData,
+        now_ms: Option<f64>,
+    ) -> Result<Vec<BarcodeResult>, BarcodeError> {
+        self.scan(
+            &image_data.data(),
+            image_data.width(),
+            image_data.height(),
+            None,
+            PixelFormat::Rgba8,
+            now_ms,
+        )
+    }
+
+    /// Scan the current contents of a canvas, see `scan`
+    pub fn scan_canvas(
+        &mut self,
+        canvas: &web_sys::HtmlCanvasElement,
+        now_ms: Option<f64>,
+    ) -> Result<Vec<BarcodeResult>, JsValue> {
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| BarcodeError::illegal_argument("no 2d context available"))?
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+        let image_data =
+            context.get_image_data(0.0, 0.0, canvas.width() as f64, canvas.height() as f64)?;
+        Ok(self.scan_image_data(&image_data, now_ms)?)
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "ScannedSymbol[]")]
+    /// The symbols read within the window or still cooling down, with where each was last seen
+    pub fn symbols(&self) -> js_sys::Array {
+        self.candidates
+            .iter()
+            .map(|candidate| {
+                let symbol = js_sys::Object::new();
+                set(&symbol, "result", candidate.result.clone().into());
+                set(&symbol, "hits", candidate.hits().into());
+                set(&symbol, "inView", candidate.in_view().into());
+                set(&symbol, "confirmed", candidate.reported.into());
+                set(&symbol, "lastSeen", candidate.last_seen_ms.into());
+                JsValue::from(symbol)
+            })
+            .collect()
+    }
+
+    /// Forget every symbol, so the next reads start from scratch and nothing is cooling down
+    pub fn reset(&mut self) {
+        self.candidates.clear();
+    }
+
+    /// Replace the hints used to decode later frames
+    pub fn set_hints(&mut self, hints: &DecodeHintDictionary) {
+        self.decoder.set_hints(hints);
+    }
+
+    /// Replace the binarizers tried on later frames
+    pub fn set_binarizers(&mut self, binarizers: Vec<Binarizer>) {
+        self.decoder.set_binarizers(binarizers);
+    }
+}
+
+impl Scanner {
+    /// Record the symbols read in a frame at `now_ms` and return the ones it confirms
+    fn step(&mut self, reads: Vec<BarcodeResult>, now_ms: f64) -> Vec<BarcodeResult> {
+        let mask = u32::MAX >> (MAX_WINDOW - self.window);
+        for candidate in &mut self.candidates {
+            candidate.history = (candidate.history << 1) & mask;
+        }
+        for read in reads {
+            match self
+                .candidates
+                .iter_mut()
+                .find(|candidate| candidate.matches(&read))
+            {
+                // a second read of the same symbol in one frame counts once
+                Some(candidate) if candidate.in_view() => {}
+                Some(candidate) => {
+                    if candidate.reported && now_ms - candidate.last_seen_ms >= self.cooldown_ms {
+                        // back after the cooldown, so it has to be confirmed again
+                        candidate.reported = false;
+                        candidate.history = 0;
+                    }
+                    candidate.history |= 1;
+                    candidate.result = read;
+                    candidate.last_seen_ms = now_ms;
+                }
+                None => self.candidates.push(Candidate {
+                    result: read,
+                    history: 1,
+                    last_seen_ms: now_ms,
+                    reported: false,
+                }),
+            }
+        }
+
+        let mut confirmed = Vec::new();
+        for candidate in &mut self.candidates {
+            if candidate.in_view() && !candidate.reported && candidate.hits() >= self.confirmations
+            {
+                candidate.reported = true;
+                confirmed.push(candidate.result.clone());
+            }
+        }
+        let cooldown_ms = self.cooldown_ms;
+        self.candidates.retain(|candidate| {
+            candidate.history != 0
+                || (candidate.reported && now_ms - candidate.last_seen_ms < cooldown_ms)
+        });
+        confirmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_scanner(confirmations: u32, window: u32, cooldown_ms: f64) -> Scanner {
+        let mut options = ScannerOptions::new();
+        options.set_confirmations(confirmations);
+        options.set_window(window);
+        options.set_cooldown_ms(cooldown_ms);
+        Scanner::new(&DecodeHintDictionary::new(), Some(options), None, None).unwrap()
+    }
+
+    fn read(text: &str) -> BarcodeResult {
+        rxing::RXingResult::new(text, Vec::new(), Vec::new(), rxing::BarcodeFormat::QR_CODE).into()
+    }
+
+    /// Step a frame in which `texts` were read and return the texts it confirms
+    fn step(scanner: &mut Scanner, texts: &[&str], now_ms: f64) -> Vec<String> {
+        let reads = texts.iter().map(|text| read(text)).collect();
+        scanner
+            .step(reads, now_ms)
+            .into_iter()
+            .map(|result| result.text)
+            .collect()
+    }
+
+    #[test]
+    fn confirms_after_n_frames_in_a_row() {
+        let mut scanner = new_scanner(3, 0, 2000.0);
+        assert!(step(&mut scanner, &["A"], 0.0).is_empty());
+        assert!(step(&mut scanner, &["A"], 100.0).is_empty());
+        assert_eq!(step(&mut scanner, &["A"], 200.0), ["A"]);
+
+        // a frame without the symbol breaks the run
+        let mut scanner = new_scanner(3, 0, 2000.0);
+        assert!(step(&mut scanner, &["B"], 0.0).is_empty());
+        assert!(step(&mut scanner, &["B"], 100.0).is_empty());
+        assert!(step(&mut scanner, &[], 200.0).is_empty());
+        assert!(step(&mut scanner, &["B"], 300.0).is_empty());
+        assert!(step(&mut scanner, &["B"], 400.0).is_empty());
+        assert_eq!(step(&mut scanner, &["B"], 500.0), ["B"]);
+    }
+
+    #[test]
+    fn confirms_after_m_of_k_frames() {
+        let mut scanner = new_scanner(2, 4, 2000.0);
+        assert!(step(&mut scanner, &["A"], 0.0).is_empty());
+        assert!(step(&mut scanner, &[], 100.0).is_empty());
+        assert!(step(&mut scanner, &[], 200.0).is_empty());
+        assert_eq!(step(&mut scanner, &["A"], 300.0), ["A"]);
+
+        // the first read has left the window by the time the second arrives
+        let mut scanner = new_scanner(2, 4, 2000.0);
+        assert!(step(&mut scanner, &["B"], 0.0).is_empty());
+        for frame in 1..4 {
+            assert!(step(&mut scanner, &[], frame as f64 * 100.0).is_empty());
+        }
+        assert!(step(&mut scanner, &["B"], 400.0).is_empty());
+        assert_eq!(step(&mut scanner, &["B"], 500.0), ["B"]);
+    }
+
+    #[test]
+    fn repeats_are_suppressed_within_the_cooldown() {
+        let mut scanner = new_scanner(1, 0, 1000.0);
+        assert_eq!(step(&mut scanner, &["A"], 0.0), ["A"]);
+        // held in view well past the cooldown
+        for frame in 1..30 {
+            assert!(step(&mut scanner, &["A"], frame as f64 * 100.0).is_empty());
+        }
+        // briefly out of view
+        assert!(step(&mut scanner, &[], 3000.0).is_empty());
+        assert!(step(&mut scanner, &["A"], 3500.0).is_empty());
+    }
+
+    #[test]
+    fn symbols_are_confirmed_again_after_the_cooldown() {
+        let mut scanner = new_scanner(2, 0, 1000.0);
+        assert!(step(&mut scanner, &["A"], 0.0).is_empty());
+        assert_eq!(step(&mut scanner, &["A"], 100.0), ["A"]);
+        // back after the cooldown, so one read is not enough
+        assert!(step(&mut scanner, &["A"], 1100.0).is_empty());
+        assert_eq!(step(&mut scanner, &["A"], 1200.0), ["A"]);
+
+        // also once the symbol has been dropped altogether
+        assert!(step(&mut scanner, &[], 1300.0).is_empty());
+        assert!(step(&mut scanner, &[], 2300.0).is_empty());
+        assert!(scanner.candidates.is_empty());
+        assert!(step(&mut scanner, &["A"], 2400.0).is_empty());
+        assert_eq!(step(&mut scanner, &["A"], 2500.0), ["A"]);
+    }
+
+    #[test]
+    fn duplicate_reads_in_one_frame_count_once() {
+        let mut scanner = new_scanner(2, 0, 2000.0);
+        assert!(step(&mut scanner, &["A", "A"], 0.0).is_empty());
+        assert_eq!(step(&mut scanner, &["A", "A", "B"], 100.0), ["A"]);
+        assert_eq!(step(&mut scanner, &["B", "B"], 200.0), ["B"]);
+
+        let mut scanner = new_scanner(1, 0, 2000.0);
+        assert_eq!(step(&mut scanner, &["A", "A"], 0.0), ["A"]);
+    }
+}
@@ -0,0 +1,715 @@
+use rxing::DecodeHints;
+use wasm_bindgen::prelude::*;
+
+use crate::binarizer::{Binarizer, Binarizers};
+use crate::deadline::{Deadline, DecodeLimits};
+use crate::decode_hints::DecodeHintDictionary;
+use crate::error::BarcodeError;
+use crate::geometry;
+use crate::region::Region;
+use crate::result_metadata::set;
+use crate::BarcodeResult;
+
+/// An (x, y) position in image coordinates
+type Point = (f32, f32);
+
+/// How far the predicted region of interest reaches past each symbol, as a fraction of the
+/// symbol's size
+const REGION_MARGIN: f32 = 0.5;
+
+/// A region of interest covering more of the frame than this is no quicker than the whole frame
+const MAX_REGION_FRACTION: f32 = 0.5;
+
+/// How much of the latest measured velocity goes into a track's velocity, the rest is kept from
+/// earlier frames to smooth out jitter in the corners
+const VELOCITY_SMOOTHING: f32 = 0.5;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TRACKED_SYMBOL: &'static str = r#"
+/**
+ * A symbol followed by a `Tracker`. `"new"` symbols were first read in this frame, `"tracked"` ones
+ * were read again, `"missing"` ones were not read this frame but may come back, and `"lost"` ones
+ * have been missing for too long and are forgotten after this frame.
+ */
+export interface TrackedSymbol {
+    id: number;
+    state: "new" | "tracked" | "missing" | "lost";
+    /** The latest read of the symbol */
+    result: BarcodeResult;
+    /** Where the symbol is in this frame, as four (x,y) pairs like `corner_points`. Predicted from its motion when it wasn't read */
+    corners: Float32Array;
+    /** How fast the symbol is moving, in pixels per second along x and y */
+    velocity: [number, number];
+    firstSeen: number;
+    lastSeen: number;
+    /** How many frames in a row it has not been read in */
+    missedFrames: number;
+}
+"#;
+
+#[wasm_bindgen]
+#[derive(Clone)]
+/// Settings for a `Tracker`
+pub struct TrackerOptions {
+    max_missed_frames: u32,
+    min_overlap: f32,
+    full_scan_interval: u32,
+}
+
+impl Default for TrackerOptions {
+    fn default() -> Self {
+        Self {
+            max_missed_frames: 5,
+            min_overlap: 0.3,
+            full_scan_interval: 10,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TrackerOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TrackerOptions {
+        TrackerOptions::default()
+    }
+
+    /// How many frames in a row a symbol can go unread before it is lost, defaults to 5
+    #[wasm_bindgen(getter)]
+    pub fn max_missed_frames(&self) -> u32 {
+        self.max_missed_frames
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_missed_frames(&mut self, max_missed_frames: u32) {
+        self.max_missed_frames = max_missed_frames;
+    }
+
+    /// How much a read must overlap the predicted position of a symbol with the same contents to
+    /// continue its track, as the shared area over the area covered by both, defaults to 0.3. A
+    /// read whose center is within half a symbol of the prediction also continues it, since
+    /// linear barcodes are only located along the row that was scanned.
+    #[wasm_bindgen(getter)]
+    pub fn min_overlap(&self) -> f32 {
+        self.min_overlap
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_min_overlap(&mut self, min_overlap: f32) {
+        self.min_overlap = min_overlap;
+    }
+
+    /// The most frames in a row that only the predicted region of interest is searched, before
+    /// the whole frame is searched again for new symbols, defaults to 10. 0 searches every whole
+    /// frame.
+    #[wasm_bindgen(getter)]
+    pub fn full_scan_interval(&self) -> u32 {
+        self.full_scan_interval
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_full_scan_interval(&mut self, full_scan_interval: u32) {
+        self.full_scan_interval = full_scan_interval;
+    }
+}
+
+#[wasm_bindgen]
+/// Follows every symbol across the frames of a video, giving each one an id that stays the same
+/// while it is in view.
+///
+/// Each frame is searched with `decode_multi`. Reads are matched to the symbols of earlier frames
+/// with the same format and text, by how well they overlap where each symbol's motion predicts
+/// it to be, so identical labels side by side keep their own ids. While every known symbol keeps
+/// turning up, only the region around their predicted positions is searched, with the whole
+/// frame searched every few frames and whenever one goes missing.
+pub struct Tracker {
+    hints: DecodeHints,
+    limits: DecodeLimits,
+    filter_image: Option<bool>,
+    binarizers: Binarizers,
+    max_missed_frames: u32,
+    min_overlap: f32,
+    full_scan_interval: u32,
+    tracks: Vec<Track>,
+    next_id: u32,
+    frames_since_full_scan: u32,
+    frame_size: (u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackState {
+    New,
+    Tracked,
+    Missing,
+    Lost,
+}
+
+impl TrackState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TrackState::New => "new",
+            TrackState::Tracked => "tracked",
+            TrackState::Missing => "missing",
+            TrackState::Lost => "lost",
+        }
+    }
+}
+
+struct Track {
+    id: u32,
+    state: TrackState,
+    /// The latest read
+    result: BarcodeResult,
+    /// The corners of the latest read, `None` if the reader reported no points
+    corners: Option<[Point; 4]>,
+    /// Pixels per millisecond
+    velocity: Point,
+    first_seen_ms: f64,
+    last_seen_ms: f64,
+    missed_frames: u32,
+}
+
+impl Track {
+    fn same_contents(&self, result: &BarcodeResult) -> bool {
+        self.result.format == result.format && self.result.text == result.text
+    }
+
+    /// Where the symbol should be at `now_ms` if it has kept moving as it was
+    fn predicted_corners(&self, now_ms: f64) -> Option<[Point; 4]> {
+        let elapsed = (now_ms - self.last_seen_ms).max(0.0) as f32;
+        let (dx, dy) = (self.velocity.0 * elapsed, self.velocity.1 * elapsed);
+        self.corners
+            .map(|corners| corners.map(|(x, y)| (x + dx, y + dy)))
+    }
+
+    /// Continue the track with `result`, read at `now_ms`
+    fn update(&mut self, result: BarcodeResult, now_ms: f64) {
+        let corners = result.corners();
+        let elapsed = (now_ms - self.last_seen_ms) as f32;
+        if let (Some(previous), Some(current)) = (self.corners, corners) {
+            if elapsed > 0.0 {
+                let (from_x, from_y) = geometry::center(&previous);
+                let (to_x, to_y) = geometry::center(&current);
+                let measured = ((to_x - from_x) / elapsed, (to_y - from_y) / elapsed);
+                self.velocity = (
+                    self.velocity.0 * (1.0 - VELOCITY_SMOOTHING) + measured.0 * VELOCITY_SMOOTHING,
+                    self.velocity.1 * (1.0 - VELOCITY_SMOOTHING) + measured.1 * VELOCITY_SMOOTHING,
+                );
+            }
+        }
+        self.state = TrackState::Tracked;
+        self.result = result;
+        self.corners = corners.or(self.corners);
+        self.last_seen_ms = now_ms;
+        self.missed_frames = 0;
+    }
+
+    fn to_js(&self, now_ms: f64) -> JsValue {
+        let symbol = js_sys::Object::new();
+        let corners: Vec<f32> = self
+            .predicted_corners(now_ms)
+            .map(|corners| corners.iter().flat_map(|(x, y)| [*x, *y]).collect())
+            .unwrap_or_default();
+        let velocity: js_sys::Array = [self.velocity.0, self.velocity.1]
+            .iter()
+            .map(|speed| JsValue::from(speed * 1000.0))
+            .collect();
+        set(&symbol, "id", self.id.into());
+        set(&symbol, "state", self.state.as_str().into());
+        set(&symbol, "result", self.result.clone().into());
+        set(&symbol, "corners", corners.into());
+        set(&symbol, "velocity", velocity.into());
+        set(&symbol, "firstSeen", self.first_seen_ms.into());
+        set(&symbol, "lastSeen", self.last_seen_ms.into());
+        set(&symbol, "missedFrames", self.missed_frames.into());
+        symbol.into()
+    }
+}
+
+#[wasm_bindgen]
+impl Tracker {
+    /// Create a tracker that searches frames with a copy of `hints`. `filter_image` and
+    /// `binarizers` are used as in `decode_multi_with_binarizers`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        hints: &DecodeHintDictionary,
+        options: Option<TrackerOptions>,
+        filter_image: Option<bool>,
+        binarizers: Option<Vec<Binarizer>>,
+    ) -> Result<Tracker, BarcodeError> {
+        let options = options.unwrap_or_default();
+        if !(0.0..=1.0).contains(&options.min_overlap) {
+            return Err(BarcodeError::illegal_argument(
+                "min_overlap must be between 0 and 1",
+            ));
+        }
+        Ok(Tracker {
+            hints: hints.get_dictionary().clone(),
+            limits: hints.limits().clone(),
+            filter_image,
+            binarizers: hints.binarizers(binarizers.unwrap_or_default()),
+            max_missed_frames: options.max_missed_frames,
+            min_overlap: options.min_overlap,
+            full_scan_interval: options.full_scan_interval,
+            tracks: Vec::new(),
+            next_id: 1,
+            frames_since_full_scan: 0,
+            frame_size: (0, 0),
+        })
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "TrackedSymbol[]")]
+    /// Search a frame of 8bit luma data and update the tracks, returning every symbol being
+    /// tracked and the ones lost in this frame.
+    ///
+    /// `now_ms` is the time of the frame in milliseconds on any steady clock, such as the
+    /// timestamp passed to a `requestVideoFrameCallback` callback, and defaults to `Date.now()`.
+    pub fn track(
+        &mut self,
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        now_ms: Option<f64>,
+    ) -> Result<js_sys::Array, BarcodeError> {
+        let now_ms = now_ms.unwrap_or_else(js_sys::Date::now);
+        self.step(data, width, height, now_ms)?;
+        let symbols = self
+            .tracks
+            .iter()
+            .map(|track| track.to_js(now_ms))
+            .collect();
+        Ok(symbols)
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "TrackedSymbol[]")]
+    /// Search a frame from an `ImageData`, see `track`
+    pub fn track_image_data(
+        &mut self,
+        image_data: &web_sys::ImageData,
+        now_ms: Option<f64>,
+    ) -> Result<js_sys::Array, BarcodeError> {
+        let luma = crate::convert_imagedata_to_luma(image_data);
+        self.track(luma, image_data.width(), image_data.height(), now_ms)
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "TrackedSymbol[]")]
+    /// The symbols being tracked as of the latest frame
+    pub fn tracks(&self) -> js_sys::Array {
+        self.tracks
+            .iter()
+            .map(|track| track.to_js(track.last_seen_ms))
+            .collect()
+    }
+
+    /// The part of a frame taken at `now_ms` where the tracked symbols are predicted to be, with
+    /// a margin around each, or nothing if it would cover most of the frame or no symbol has a
+    /// known position. `now_ms` defaults to `Date.now()`.
+    pub fn predicted_region(&self, now_ms: Option<f64>) -> Option<Region> {
+        let now_ms = now_ms.unwrap_or_else(js_sys::Date::now);
+        let mut extent: Option<(f32, f32, f32, f32)> = None;
+        for track in &self.tracks {
+            let Some(corners) = track.predicted_corners(now_ms) else {
+                continue;
+            };
+            let (left, top, right, bottom) = geometry::extent(&corners);
+            let margin = (right - left).max(bottom - top) * REGION_MARGIN;
+            let padded = (left - margin, top - margin, right + margin, bottom + margin);
+            extent = Some(match extent {
+                None => padded,
+                Some((l, t, r, b)) => (
+                    l.min(padded.0),
+                    t.min(padded.1),
+                    r.max(padded.2),
+                    b.max(padded.3),
+                ),
+            });
+        }
+        let (left, top, right, bottom) = extent?;
+        let (width, height) = self.frame_size;
+        let left = left.max(0.0).floor() as u32;
+        let top = top.max(0.0).floor() as u32;
+        let right = (right.ceil() as u32).min(width);
+        let bottom = (bottom.ceil() as u32).min(height);
+        if right <= left || bottom <= top {
+            return None;
+        }
+        let area = (right - left) as f32 * (bottom - top) as f32;
+        if area > width as f32 * height as f32 * MAX_REGION_FRACTION {
+            return None;
+        }
+        Some(Region::new(left, top, right - left, bottom - top))
+    }
+
+    /// Forget every track. Ids keep counting up, so they are never reused.
+    pub fn reset(&mut self) {
+        self.tracks.clear();
+        self.frames_since_full_scan = 0;
+    }
+
+    /// Replace the hints used to search later frames
+    pub fn set_hints(&mut self, hints: &DecodeHintDictionary) {
+        self.hints = hints.get_dictionary().clone();
+        self.limits = hints.limits().clone();
+        self.binarizers.set_fixed_threshold(hints.fixed_threshold());
+    }
+}
+
+impl Tracker {
+    /// Search a frame taken at `now_ms` and update the tracks with what it finds. Tracks lost in
+    /// the frame before are dropped first, so each lost track is reported once.
+    fn step(
+        &mut self,
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        now_ms: f64,
+    ) -> Result<(), BarcodeError> {
+        self.tracks.retain(|track| track.state != TrackState::Lost);
+        if self.frame_size != (width, height) {
+            // positions from frames of another size mean nothing in this one
+            self.frames_since_full_scan = self.full_scan_interval;
+            self.frame_size = (width, height);
+        }
+        // both passes of a frame share its budget
+        let deadline = Deadline::start(&self.limits);
+        let reads = self.search(data, width, height, now_ms, &deadline)?;
+        self.assign(reads, now_ms);
+        Ok(())
+    }
+
+    /// Read the symbols in a frame, from the predicted region of interest when that finds every
+    /// tracked symbol and the whole frame has been searched recently enough. When the region uses
+    /// up the frame's budget the whole frame is searched on the next one instead.
+    fn search(
+        &mut self,
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        now_ms: f64,
+        deadline: &Deadline,
+    ) -> Result<Vec<BarcodeResult>, BarcodeError> {
+        let mut reads = Vec::new();
+        if self.frames_since_full_scan < self.full_scan_interval {
+            if let Some(region) = self.predicted_region(Some(now_ms)) {
+                let cropped = region.crop_luma(&data, width, height)?;
+                reads = self.detect(cropped, region.width, region.height, deadline)?;
+                for read in &mut reads {
+                    read.translate(region.x as f32, region.y as f32);
+                }
+                // identical labels each need a read of their own
+                let all_found = self.matches(&reads, now_ms).iter().all(Option::is_some);
+                if all_found {
+                    self.frames_since_full_scan += 1;
+                    return Ok(reads);
+                }
+                if deadline.passed() {
+                    self.frames_since_full_scan = self.full_scan_interval;
+                    return Ok(reads);
+                }
+            }
+        }
+        self.frames_since_full_scan = 0;
+        let mut full = self.detect(data, width, height, deadline)?;
+        // the crop can find symbols the whole frame misses, keep those
+        for read in reads {
+            let found = full.iter().any(|other| {
+                other.format == read.format
+                    && other.text == read.text
+                    && same_place(read.corners(), other.corners(), self.min_overlap)
+            });
+            if !found {
+                full.push(read);
+            }
+        }
+        Ok(full)
+    }
+
+    fn detect(
+        &self,
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        deadline: &Deadline,
+    ) -> Result<Vec<BarcodeResult>, BarcodeError> {
+        let mut hints = self.hints.clone();
+        match crate::detect_multiple_in_luma(
+            data,
+            width,
+            height,
+            &mut hints,
+            deadline,
+            self.filter_image,
+            &self.binarizers,
+        ) {
+            Ok(results) => Ok(results.into_iter().map(BarcodeResult::from).collect()),
+            Err(error) if error.is_unreadable() => Ok(Vec::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Pair the tracks with the reads of a frame at `now_ms`, nearest first. A read can only
+    /// continue a track with the same contents that it is in the same place as, and each read
+    /// continues at most one track. Returns the index of the read for each track.
+    fn matches(&self, reads: &[BarcodeResult], now_ms: f64) -> Vec<Option<usize>> {
+        let mut pairs = Vec::new();
+        for (track_index, track) in self.tracks.iter().enumerate() {
+            let predicted = track.predicted_corners(now_ms);
+            for (read_index, read) in reads.iter().enumerate() {
+                if !track.same_contents(read) {
+                    continue;
+                }
+                let distance = match (predicted, read.corners()) {
+                    (Some(expected), Some(found)) => {
+                        if !same_place(Some(expected), Some(found), self.min_overlap) {
+                            continue;
+                        }
+                        let (x1, y1) = geometry::center(&expected);
+                        let (x2, y2) = geometry::center(&found);
+                        (x2 - x1).hypot(y2 - y1)
+                    }
+                    // without a position only the contents can match, and any positioned match
+                    // is better
+                    _ => f32::MAX,
+                };
+                pairs.push((distance, track_index, read_index));
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut matches = vec![None; self.tracks.len()];
+        let mut taken = vec![false; reads.len()];
+        for (_, track_index, read_index) in pairs {
+            if matches[track_index].is_none() && !taken[read_index] {
+                matches[track_index] = Some(read_index);
+                taken[read_index] = true;
+            }
+        }
+        matches
+    }
+
+    /// Continue the tracks with the reads of a frame at `now_ms` as `matches` pairs them, start
+    /// new tracks for the rest and count a missed frame for every track left without a read
+    fn assign(&mut self, reads: Vec<BarcodeResult>, now_ms: f64) {
+        let matches = self.matches(&reads, now_ms);
+        let mut reads: Vec<Option<BarcodeResult>> = reads.into_iter().map(Some).collect();
+        for (track, read_index) in self.tracks.iter_mut().zip(matches) {
+            match read_index.and_then(|index| reads[index].take()) {
+                Some(read) => track.update(read, now_ms),
+                None => {
+                    track.missed_frames += 1;
+                    track.state = if track.missed_frames > self.max_missed_frames {
+                        TrackState::Lost
+                    } else {
+                        TrackState::Missing
+                    };
+                }
+            }
+        }
+        for read in reads.into_iter().flatten() {
+            self.tracks.push(Track {
+                id: self.next_id,
+                state: TrackState::New,
+                corners: read.corners(),
+                result: read,
+                velocity: (0.0, 0.0),
+                first_seen_ms: now_ms,
+                last_seen_ms: now_ms,
+                missed_frames: 0,
+            });
+            self.next_id += 1;
+        }
+    }
+}
+
+/// Whether two sightings of symbols with the same contents are of the same symbol: their quads
+/// overlap by at least `min_overlap`, or their centers are within half the longest side of
+/// either. Sightings without a position can't be told apart.
+fn same_place(a: Option<[Point; 4]>, b: Option<[Point; 4]>, min_overlap: f32) -> bool {
+    let (Some(a), Some(b)) = (a, b) else {
+        return true;
+    };
+    if geometry::overlap(&a, &b) >= min_overlap.max(f32::MIN_POSITIVE) {
+        return true;
+    }
+    let (x1, y1) = geometry::center(&a);
+    let (x2, y2) = geometry::center(&b);
+    (x2 - x1).hypot(y2 - y1) <= longest_side(&a).max(longest_side(&b)) / 2.0
+}
+
+fn longest_side(corners: &[Point; 4]) -> f32 {
+    corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|((x1, y1), (x2, y2))| (x2 - x1).hypot(y2 - y1))
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The side of the symbols in these tests
+    const SIZE: f32 = 40.0;
+
+    fn tracker() -> Tracker {
+        let mut tracker = Tracker::new(&DecodeHintDictionary::new(), None, None, None).unwrap();
+        tracker.frame_size = (640, 480);
+        tracker
+    }
+
+    /// A read of a QR code with its top-left corner at `x`, `y`
+    fn read(text: &str, x: f32, y: f32) -> BarcodeResult {
+        let corners = [(x, y), (x + SIZE, y), (x + SIZE, y + SIZE), (x, y + SIZE)];
+        rxing::RXingResult::new(
+            text,
+            Vec::new(),
+            corners
+                .iter()
+                .map(|(x, y)| rxing::Point { x: *x, y: *y })
+                .collect(),
+            rxing::BarcodeFormat::QR_CODE,
+        )
+        .into()
+    }
+
+    fn states(tracker: &Tracker) -> Vec<(u32, TrackState)> {
+        tracker
+            .tracks
+            .iter()
+            .map(|track| (track.id, track.state))
+            .collect()
+    }
+
+    fn left_of(tracker: &Tracker, id: u32) -> f32 {
+        let track = tracker.tracks.iter().find(|track| track.id == id).unwrap();
+        track.corners.unwrap()[0].0
+    }
+
+    #[test]
+    fn identical_labels_keep_their_own_ids() {
+        let mut tracker = tracker();
+        tracker.assign(vec![read("A", 100.0, 100.0), read("A", 300.0, 100.0)], 0.0);
+        assert_eq!(
+            states(&tracker),
+            [(1, TrackState::New), (2, TrackState::New)]
+        );
+        // both move right, and the reader lists them the other way round
+        tracker.assign(
+            vec![read("A", 310.0, 100.0), read("A", 110.0, 100.0)],
+            100.0,
+        );
+        assert_eq!(
+            states(&tracker),
+            [(1, TrackState::Tracked), (2, TrackState::Tracked)]
+        );
+        assert_eq!(left_of(&tracker, 1), 110.0);
+        assert_eq!(left_of(&tracker, 2), 310.0);
+    }
+
+    #[test]
+    fn one_read_of_identical_labels_continues_only_one() {
+        let mut tracker = tracker();
+        tracker.assign(vec![read("A", 100.0, 100.0), read("A", 300.0, 100.0)], 0.0);
+        let reads = [read("A", 300.0, 100.0)];
+        // the region search has not found every track, so the whole frame is searched
+        assert_eq!(tracker.matches(&reads, 100.0), [None, Some(0)]);
+        tracker.assign(reads.to_vec(), 100.0);
+        assert_eq!(
+            states(&tracker),
+            [(1, TrackState::Missing), (2, TrackState::Tracked)]
+        );
+    }
+
+    #[test]
+    fn a_read_elsewhere_starts_a_new_track() {
+        let mut tracker = tracker();
+        tracker.assign(vec![read("A", 100.0, 100.0)], 0.0);
+        tracker.assign(
+            vec![read("A", 400.0, 300.0), read("B", 100.0, 100.0)],
+            100.0,
+        );
+        assert_eq!(
+            states(&tracker),
+            [
+                (1, TrackState::Missing),
+                (2, TrackState::New),
+                (3, TrackState::New)
+            ]
+        );
+    }
+
+    #[test]
+    fn a_moving_symbol_keeps_its_id() {
+        let mut tracker = tracker();
+        for frame in 0..10 {
+            let x = 100.0 + frame as f32 * 15.0;
+            tracker.assign(vec![read("A", x, 100.0)], frame as f64 * 100.0);
+        }
+        assert_eq!(states(&tracker), [(1, TrackState::Tracked)]);
+        let (velocity_x, velocity_y) = tracker.tracks[0].velocity;
+        assert!((velocity_x - 0.15).abs() < 0.001);
+        assert_eq!(velocity_y, 0.0);
+        // after a missed frame it has moved 30 pixels, too far to overlap where it was last seen
+        // but where its motion predicts it to be
+        tracker.assign(Vec::new(), 1000.0);
+        tracker.assign(vec![read("A", 265.0, 100.0)], 1100.0);
+        assert_eq!(states(&tracker), [(1, TrackState::Tracked)]);
+    }
+
+    #[test]
+    fn a_missing_track_is_lost_after_max_missed_frames() {
+        let mut tracker = tracker();
+        tracker.assign(vec![read("A", 100.0, 100.0)], 0.0);
+        for frame in 1..=5 {
+            tracker.assign(Vec::new(), frame as f64 * 100.0);
+            assert_eq!(states(&tracker), [(1, TrackState::Missing)]);
+            assert_eq!(tracker.tracks[0].missed_frames, frame);
+        }
+        tracker.assign(Vec::new(), 600.0);
+        assert_eq!(states(&tracker), [(1, TrackState::Lost)]);
+    }
+
+    #[test]
+    fn a_symbol_back_before_it_is_lost_keeps_its_id() {
+        let mut tracker = tracker();
+        tracker.assign(vec![read("A", 100.0, 100.0)], 0.0);
+        tracker.assign(Vec::new(), 100.0);
+        tracker.assign(vec![read("A", 102.0, 100.0)], 200.0);
+        assert_eq!(states(&tracker), [(1, TrackState::Tracked)]);
+        assert_eq!(tracker.tracks[0].missed_frames, 0);
+    }
+
+    #[test]
+    fn ids_are_not_reused_after_a_reset() {
+        let mut tracker = tracker();
+        tracker.assign(vec![read("A", 100.0, 100.0)], 0.0);
+        tracker.reset();
+        tracker.assign(vec![read("A", 100.0, 100.0)], 100.0);
+        assert_eq!(states(&tracker), [(2, TrackState::New)]);
+    }
+
+    #[test]
+    fn predicts_the_region_around_the_symbols() {
+        let mut tracker = tracker();
+        assert_eq!(tracker.predicted_region(Some(0.0)), None);
+        tracker.assign(vec![read("A", 100.0, 100.0), read("B", 200.0, 100.0)], 0.0);
+        let region = tracker.predicted_region(Some(0.0)).unwrap();
+        // half a symbol of margin around both
+        assert_eq!(
+            (region.x, region.y, region.width, region.height),
+            (80, 80, 180, 80)
+        );
+        // symbols spread over the frame leave nothing to save
+        tracker.assign(vec![read("C", 590.0, 430.0)], 0.0);
+        assert_eq!(tracker.predicted_region(Some(0.0)), None);
+    }
+
+    #[test]
+    fn same_place_by_overlap_or_distance() {
+        let corners = |x: f32| read("A", x, 0.0).corners();
+        assert!(same_place(corners(0.0), corners(10.0), 0.3));
+        // no overlap to speak of, but the centers are within half a symbol
+        assert!(same_place(corners(0.0), corners(20.0), 0.9));
+        assert!(!same_place(corners(0.0), corners(30.0), 0.3));
+        assert!(same_place(None, corners(300.0), 0.3));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::BarcodeError;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+/// Where the luma (Y) plane of a YUV frame lives in its buffer, and which part of it is visible.
+///
+/// I420, NV12 and NV21 all store a full resolution Y plane, which is exactly the luma a decode
+/// needs, so the chroma planes are never read. The layout can be reused for every frame of a
+/// stream.
+pub struct YuvLayout {
+    y_offset: u32,
+    y_stride: u32,
+    visible_x: u32,
+    visible_y: u32,
+    visible_width: u32,
+    visible_height: u32,
+}
+
+#[wasm_bindgen]
+impl YuvLayout {
+    /// A tightly packed Y plane at the start of the buffer, with the whole frame visible
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u32, height: u32) -> YuvLayout {
+        YuvLayout {
+            y_offset: 0,
+            y_stride: width,
+            visible_x: 0,
+            visible_y: 0,
+            visible_width: width,
+            visible_height: height,
+        }
+    }
+
+    /// Offset, in bytes, of the Y plane in the buffer. `PlaneLayout.offset` from `VideoFrame.copyTo`.
+    #[wasm_bindgen(getter)]
+    pub fn y_offset(&self) -> u32 {
+        self.y_offset
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_y_offset(&mut self, y_offset: u32) {
+        self.y_offset = y_offset;
+    }
+
+    /// Bytes from the start of one row of the Y plane to the next. `PlaneLayout.stride` from
+    /// `VideoFrame.copyTo`.
+    #[wasm_bindgen(getter)]
+    pub fn y_stride(&self) -> u32 {
+        self.y_stride
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_y_stride(&mut self, y_stride: u32) {
+        self.y_stride = y_stride;
+    }
+
+    /// Restrict decoding to the visible rectangle of the frame, such as `VideoFrame.visibleRect`
+    pub fn set_visible_rect(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        self.visible_x = x;
+        self.visible_y = y;
+        self.visible_width = width;
+        self.visible_height = height;
+    }
+}
+
+impl YuvLayout {
+    pub(crate) fn visible_size(&self) -> (u32, u32) {
+        (self.visible_width, self.visible_height)
+    }
+
+    /// Copy the visible part of the Y plane out of `data` as tightly packed luma 8
+    pub(crate) fn extract_luma(&self, data: &[u8]) -> Result<Vec<u8>, BarcodeError> {
+        if self.visible_width == 0 || self.visible_height == 0 {
+            return Err(BarcodeError::invalid_input(format!(
+                "visible rectangle of {}x{} pixels is empty",
+                self.visible_width, self.visible_height
+            )));
+        }
+        // sizes are worked out in 64 bits so they can't overflow a 32 bit usize
+        let width = self.visible_width as u64;
+        let height = self.visible_height as u64;
+        let stride = self.y_stride as u64;
+        let row_end = self.visible_x as u64 + width;
+        if row_end > stride {
+            return Err(BarcodeError::invalid_input(format!(
+                "visible rectangle ends at column {row_end}, past the Y stride of {stride} bytes"
+            )));
+        }
+
+        let start = self.y_offset as u64 + self.visible_y as u64 * stride + self.visible_x as u64;
+        let required = start + stride * (height - 1) + width;
+        if (data.len() as u64) < required {
+            return Err(BarcodeError::invalid_input(format!(
+                "buffer of {} bytes is too small for the Y plane and visible rectangle ({required} bytes)",
+                data.len()
+            )));
+        }
+        // everything read lies inside the buffer, so fits in a usize
+        let (width, height, stride, start) = (
+            width as usize,
+            height as usize,
+            stride as usize,
+            start as usize,
+        );
+
+        let mut luma = Vec::with_capacity(width * height);
+        for row in 0..height {
+            let row_start = start + row * stride;
+            luma.extend_from_slice(&data[row_start..row_start + width]);
+        }
+        Ok(luma)
+    }
+}